@@ -0,0 +1,259 @@
+/// ID3v1 genres, including the Winamp extensions.
+const ID3V1_GENRES: [&str; 192] = [
+    "Blues",
+    "Classic Rock",
+    "Country",
+    "Dance",
+    "Disco",
+    "Funk",
+    "Grunge",
+    "Hip-Hop",
+    "Jazz",
+    "Metal",
+    "New Age",
+    "Oldies",
+    "Other",
+    "Pop",
+    "R&B",
+    "Rap",
+    "Reggae",
+    "Rock",
+    "Techno",
+    "Industrial",
+    "Alternative",
+    "Ska",
+    "Death Metal",
+    "Pranks",
+    "Soundtrack",
+    "Euro-Techno",
+    "Ambient",
+    "Trip-Hop",
+    "Vocal",
+    "Jazz+Funk",
+    "Fusion",
+    "Trance",
+    "Classical",
+    "Instrumental",
+    "Acid",
+    "House",
+    "Game",
+    "Sound Clip",
+    "Gospel",
+    "Noise",
+    "Alternative Rock",
+    "Bass",
+    "Soul",
+    "Punk",
+    "Space",
+    "Meditative",
+    "Instrumental Pop",
+    "Instrumental Rock",
+    "Ethnic",
+    "Gothic",
+    "Darkwave",
+    "Techno-Industrial",
+    "Electronic",
+    "Pop-Folk",
+    "Eurodance",
+    "Dream",
+    "Southern Rock",
+    "Comedy",
+    "Cult",
+    "Gangsta",
+    "Top 40",
+    "Christian Rap",
+    "Pop/Funk",
+    "Jungle",
+    "Native American",
+    "Cabaret",
+    "New Wave",
+    "Psychedelic",
+    "Rave",
+    "Showtunes",
+    "Trailer",
+    "Lo-Fi",
+    "Tribal",
+    "Acid Punk",
+    "Acid Jazz",
+    "Polka",
+    "Retro",
+    "Musical",
+    "Rock & Roll",
+    "Hard Rock",
+    "Folk",
+    "Folk-Rock",
+    "National Folk",
+    "Swing",
+    "Fast Fusion",
+    "Bebop",
+    "Latin",
+    "Revival",
+    "Celtic",
+    "Bluegrass",
+    "Avantgarde",
+    "Gothic Rock",
+    "Progressive Rock",
+    "Psychedelic Rock",
+    "Symphonic Rock",
+    "Slow Rock",
+    "Big Band",
+    "Chorus",
+    "Easy Listening",
+    "Acoustic",
+    "Humour",
+    "Speech",
+    "Chanson",
+    "Opera",
+    "Chamber Music",
+    "Sonata",
+    "Symphony",
+    "Booty Bass",
+    "Primus",
+    "Porn Groove",
+    "Satire",
+    "Slow Jam",
+    "Club",
+    "Tango",
+    "Samba",
+    "Folklore",
+    "Ballad",
+    "Power Ballad",
+    "Rhythmic Soul",
+    "Freestyle",
+    "Duet",
+    "Punk Rock",
+    "Drum Solo",
+    "A Cappella",
+    "Euro-House",
+    "Dance Hall",
+    "Goa",
+    "Drum & Bass",
+    "Club-House",
+    "Hardcore",
+    "Terror",
+    "Indie",
+    "BritPop",
+    "Afro-Punk",
+    "Polsk Punk",
+    "Beat",
+    "Christian Gangsta Rap",
+    "Heavy Metal",
+    "Black Metal",
+    "Crossover",
+    "Contemporary Christian",
+    "Christian Rock",
+    "Merengue",
+    "Salsa",
+    "Thrash Metal",
+    "Anime",
+    "JPop",
+    "Synthpop",
+    "Abstract",
+    "Art Rock",
+    "Baroque",
+    "Bhangra",
+    "Big Beat",
+    "Breakbeat",
+    "Chillout",
+    "Downtempo",
+    "Dub",
+    "EBM",
+    "Eclectic",
+    "Electro",
+    "Electroclash",
+    "Emo",
+    "Experimental",
+    "Garage",
+    "Global",
+    "IDM",
+    "Illbient",
+    "Industro-Goth",
+    "Jam Band",
+    "Krautrock",
+    "Leftfield",
+    "Lounge",
+    "Math Rock",
+    "New Romantic",
+    "Nu-Breakz",
+    "Post-Punk",
+    "Post-Rock",
+    "Psytrance",
+    "Shoegaze",
+    "Space Rock",
+    "Trop Rock",
+    "World Music",
+    "Neoclassical",
+    "Audiobook",
+    "Audio Theatre",
+    "Neue Deutsche Welle",
+    "Podcast",
+    "Indie Rock",
+    "G-Funk",
+    "Dubstep",
+    "Garage Rock",
+    "Psybient",
+];
+
+fn genre_name(code: &str) -> Option<&'static str> {
+    match code {
+        "RX" => Some("Remix"),
+        "CR" => Some("Cover"),
+        _ => ID3V1_GENRES.get(code.parse::<usize>().ok()?).copied(),
+    }
+}
+
+/// Resolves the ID3v1 references in a TCON value: `17`, `(17)`, `(17)Rock`
+/// (the text refines the reference and wins), `(RX)`, `(CR)` and `((`
+/// escaped text.
+pub(crate) fn parse_id3_genre(tcon: &str) -> String {
+    let tcon = tcon.trim();
+    if let Some(name) = genre_name(tcon) {
+        return name.into();
+    }
+    let mut names = Vec::new();
+    let mut rest = tcon;
+    while let Some(tail) = rest.strip_prefix('(') {
+        if let Some(escaped) = tail.strip_prefix('(') {
+            return format!("({}", escaped);
+        }
+        match tail
+            .split_once(')')
+            .and_then(|(code, tail)| Some((genre_name(code)?, tail)))
+        {
+            Some((name, tail)) => {
+                names.push(name);
+                rest = tail;
+            }
+            None => break,
+        }
+    }
+    let refinement = rest.trim();
+    if refinement.is_empty() {
+        names.join("/")
+    } else {
+        refinement.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_id3_genre;
+
+    #[test]
+    fn resolves_references() {
+        assert_eq!(parse_id3_genre("17"), "Rock");
+        assert_eq!(parse_id3_genre("(17)"), "Rock");
+        assert_eq!(parse_id3_genre("(17)(8)"), "Rock/Jazz");
+        assert_eq!(parse_id3_genre("(RX)(CR)"), "Remix/Cover");
+        assert_eq!(parse_id3_genre("(191)"), "Psybient");
+    }
+
+    #[test]
+    fn keeps_text() {
+        assert_eq!(parse_id3_genre("(17)Hard Rock"), "Hard Rock");
+        assert_eq!(parse_id3_genre("((Not a reference)"), "(Not a reference)");
+        assert_eq!(parse_id3_genre(" Shoegaze "), "Shoegaze");
+        assert_eq!(parse_id3_genre("(192)"), "(192)");
+        assert_eq!(parse_id3_genre("(17"), "(17");
+    }
+}
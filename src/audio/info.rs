@@ -1,13 +1,22 @@
 use std::{
+    collections::HashSet,
     fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
     path::{Path, PathBuf},
 };
 
-use base64::Engine;
+use base64::{prelude::BASE64_STANDARD, Engine};
 use id3::TagLike;
-use symphonia::core::{formats::FormatReader, io::MediaSourceStream, probe::Hint};
+use symphonia::core::{
+    formats::FormatReader,
+    io::{MediaSource, MediaSourceStream},
+    probe::Hint,
+};
 
-use crate::{lyrics::Lyrics, AudioReader};
+use crate::{
+    lyrics::{LrcPrecision, Lyrics},
+    AudioReader,
+};
 
 pub(crate) fn split_artists_to_string<'a>(iter: impl Iterator<Item = &'a str>) -> Vec<String> {
     let mut artists = Vec::new();
@@ -34,7 +43,25 @@ pub(crate) fn split_artist_to_string(name: &str) -> Vec<String> {
     }
     artists
 }
-use super::{Artwork, ImgFmt};
+use super::{
+    genre::parse_id3_genre,
+    lang::{id3_lang_code, same_language},
+    ogg::VorbisComments,
+    properties::{skip_id3v2, AudioProperties},
+    Artwork, ImgFmt, PictureType,
+};
+/// The length of the ID3v2 tag at the start of `header`, footer included.
+fn id3v2_len(header: &[u8]) -> Option<usize> {
+    if header.len() < 10 || !header.starts_with(b"ID3") {
+        return None;
+    }
+    let size = header[6..10]
+        .iter()
+        .fold(0usize, |size, &b| (size << 7) | (b & 0x7F) as usize);
+    let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+    Some(10 + size + footer)
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum MusicFormat {
     M4a,
@@ -42,6 +69,82 @@ pub enum MusicFormat {
     Flac,
     Ogg,
 }
+
+impl MusicFormat {
+    /// Guesses the format from the magic bytes at the start of a file.
+    /// A dozen bytes are enough, except after an ID3v2 tag: FLAC and Ogg
+    /// files may start with one too, so the bytes following it decide.
+    /// Without them, or if they are not recognized, MP3 is assumed.
+    pub fn detect(header: &[u8]) -> Option<MusicFormat> {
+        if let Some(len) = id3v2_len(header) {
+            let after = header.get(len..).and_then(Self::detect);
+            Some(after.unwrap_or(MusicFormat::Mp3))
+        } else if header.starts_with(b"fLaC") {
+            Some(MusicFormat::Flac)
+        } else if header.starts_with(b"OggS") {
+            Some(MusicFormat::Ogg)
+        } else if header.get(4..8) == Some(b"ftyp") {
+            Some(MusicFormat::M4a)
+        } else if let [0xFF, b, ..] = header {
+            // MPEG frame sync; layer `00` is reserved and used by ADTS AAC.
+            (b & 0xE0 == 0xE0 && b & 0x06 != 0).then_some(MusicFormat::Mp3)
+        } else {
+            None
+        }
+    }
+    /// Same as [`MusicFormat::detect`], reading the header from `reader`.
+    /// The position of `reader` is left unchanged.
+    pub fn detect_from_reader(
+        reader: &mut (impl Read + Seek),
+    ) -> crate::Result<Option<MusicFormat>> {
+        let start = reader.stream_position()?;
+        skip_id3v2(reader)?;
+        let has_id3 = reader.stream_position()? != start;
+        let mut header = Vec::with_capacity(12);
+        reader.by_ref().take(12).read_to_end(&mut header)?;
+        reader.seek(SeekFrom::Start(start))?;
+        match Self::detect(&header) {
+            None if has_id3 => Ok(Some(MusicFormat::Mp3)),
+            fmt => Ok(fmt),
+        }
+    }
+    /// Maps a file extension to a format, ignoring case.
+    pub fn from_extension(ext: &str) -> Option<MusicFormat> {
+        match ext.to_ascii_lowercase().as_str() {
+            "mp3" => Some(MusicFormat::Mp3),
+            "flac" => Some(MusicFormat::Flac),
+            "m4a" | "m4b" | "m4p" | "m4r" | "mp4" => Some(MusicFormat::M4a),
+            "ogg" | "oga" => Some(MusicFormat::Ogg),
+            _ => None,
+        }
+    }
+}
+
+/// One set of lyrics stored in a file. Files may carry several, such as the
+/// original and its translations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LyricsEntry {
+    pub text: String,
+    /// The language code as stored: ISO-639-2 such as `eng` in ID3 frames,
+    /// usually ISO-639-1 such as `en` in Vorbis comments. `None` when
+    /// unknown.
+    pub lang: Option<String>,
+    /// Tells entries in the same language apart. Only ID3 stores it.
+    pub description: String,
+}
+
+impl LyricsEntry {
+    pub fn new(text: impl Into<String>) -> LyricsEntry {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+    pub fn lyrics(&self) -> Lyrics {
+        Lyrics::from(&self.text)
+    }
+}
+
 #[derive(Debug)]
 pub struct MusicTag {
     path: Option<PathBuf>,
@@ -51,8 +154,96 @@ pub struct MusicTag {
     album: Option<String>,
     album_artists: Vec<String>,
     year: Option<i32>,
+    track_number: Option<u32>,
+    track_total: Option<u32>,
+    disc_number: Option<u32>,
+    disc_total: Option<u32>,
+    genre: Option<String>,
+    composer: Option<String>,
+    lyricist: Option<String>,
+    conductor: Option<String>,
+    comment: Option<String>,
+    copyright: Option<String>,
+    publisher: Option<String>,
+    grouping: Option<String>,
+    bpm: Option<u32>,
+    encoder: Option<String>,
     artworks: Vec<Artwork>,
-    lyrics: Option<String>,
+    lyrics: Vec<LyricsEntry>,
+    properties: Option<AudioProperties>,
+    #[cfg(feature = "image")]
+    artwork_policy: Option<super::ArtworkPolicy>,
+}
+
+/// Parses `n` or `n/total`, as used by TRCK, TPOS and TRACKNUMBER.
+fn parse_number_pair(value: &str) -> (Option<u32>, Option<u32>) {
+    let mut parts = value.splitn(2, '/');
+    let number = parts.next().and_then(|s| s.trim().parse().ok());
+    let total = parts.next().and_then(|s| s.trim().parse().ok());
+    (number, total)
+}
+
+/// BPM is stored as text in ID3 and Vorbis comments, sometimes with decimals.
+fn parse_bpm(value: &str) -> Option<u32> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .map(|bpm| bpm.round() as u32)
+}
+
+const MP4_CONDUCTOR: mp4ameta::FreeformIdent =
+    mp4ameta::FreeformIdent::new("com.apple.iTunes", "CONDUCTOR");
+const MP4_LABEL: mp4ameta::FreeformIdent =
+    mp4ameta::FreeformIdent::new("com.apple.iTunes", "LABEL");
+fn id3_lang(lang: &str) -> Option<String> {
+    let lang = lang.trim_matches(char::from(0)).trim();
+    match lang.to_ascii_lowercase().as_str() {
+        "" | "und" | "xxx" => None,
+        lang => Some(lang.into()),
+    }
+}
+
+/// The language of a Vorbis lyrics field: `LYRICS` and `UNSYNCEDLYRICS`
+/// have none, `LYRICS-xx` and `LYRICS_xx` are in `xx`.
+fn vorbis_lyrics_lang(key: &str) -> Option<Option<String>> {
+    let key = key.to_ascii_uppercase();
+    match key.as_str() {
+        "LYRICS" | "UNSYNCEDLYRICS" => Some(None),
+        _ => key
+            .strip_prefix("LYRICS-")
+            .or_else(|| key.strip_prefix("LYRICS_"))
+            .filter(|lang| !lang.is_empty())
+            .map(|lang| Some(lang.to_ascii_lowercase())),
+    }
+}
+
+fn vorbis_lyrics_key(entry: &LyricsEntry) -> String {
+    match &entry.lang {
+        Some(lang) => format!("LYRICS-{}", lang.to_ascii_uppercase()),
+        None => "LYRICS".into(),
+    }
+}
+
+/// Vorbis comment fields read from the first of several keys.
+const VORBIS_COMMENT_KEYS: [&str; 2] = ["COMMENT", "DESCRIPTION"];
+const VORBIS_PUBLISHER_KEYS: [&str; 3] = ["LABEL", "PUBLISHER", "ORGANIZATION"];
+
+/// The aliases of `keys[0]` to remove when writing it: the one the value was
+/// read from, which the canonical key replaces, or all of them when the field
+/// is cleared, as they would be read back in its place.
+fn stale_aliases<'a>(
+    keys: &[&'a str],
+    cleared: bool,
+    present: impl Fn(&str) -> bool,
+) -> Vec<&'a str> {
+    if cleared {
+        return keys[1..].to_vec();
+    }
+    match keys.iter().position(|key| present(key)) {
+        Some(i) if i > 0 => vec![keys[i]],
+        _ => Vec::new(),
+    }
 }
 
 fn default_info(fmt: MusicFormat) -> MusicTag {
@@ -64,8 +255,25 @@ fn default_info(fmt: MusicFormat) -> MusicTag {
         album: None,
         album_artists: Vec::new(),
         year: None,
+        track_number: None,
+        track_total: None,
+        disc_number: None,
+        disc_total: None,
+        genre: None,
+        composer: None,
+        lyricist: None,
+        conductor: None,
+        comment: None,
+        copyright: None,
+        publisher: None,
+        grouping: None,
+        bpm: None,
+        encoder: None,
         artworks: Vec::new(),
-        lyrics: None,
+        lyrics: Vec::new(),
+        properties: None,
+        #[cfg(feature = "image")]
+        artwork_policy: None,
     }
 }
 
@@ -84,18 +292,55 @@ impl TryFrom<(id3::Tag, Option<PathBuf>)> for MusicTag {
             .map_or(Default::default(), split_artist_to_string);
         let mut artworks = Vec::new();
         for pic in tag.pictures() {
-            if let id3::frame::PictureType::CoverFront = pic.picture_type {
-                if let Ok(size) = imagesize::blob_size(&pic.data) {
-                    artworks.push(Artwork {
-                        data: pic.data.to_vec(),
-                        height: size.height,
-                        width: size.width,
-                        fmt: ImgFmt::from_mime(&pic.mime_type),
-                    })
-                }
+            if let Ok(size) = imagesize::blob_size(&pic.data) {
+                artworks.push(Artwork {
+                    data: pic.data.to_vec(),
+                    height: size.height,
+                    width: size.width,
+                    fmt: ImgFmt::detect(&pic.data),
+                    picture_type: pic.picture_type.into(),
+                    description: pic.description.clone(),
+                })
+            }
+        }
+        let mut lyrics: Vec<_> = tag
+            .lyrics()
+            .map(|uslt| LyricsEntry {
+                text: uslt.text.to_owned(),
+                lang: id3_lang(&uslt.lang),
+                description: uslt.description.to_owned(),
+            })
+            .collect();
+        // USLT keeps LRC header tags, a SYLT frame is only used when no USLT
+        // frame has the same language and description.
+        for sylt in tag.synchronised_lyrics() {
+            let entry = LyricsEntry {
+                text: String::new(),
+                lang: id3_lang(&sylt.lang),
+                description: sylt.description.to_owned(),
+            };
+            if lyrics
+                .iter()
+                .any(|e| e.lang == entry.lang && e.description == entry.description)
+            {
+                continue;
+            }
+            if let Some(timed) = Lyrics::from_sylt(sylt) {
+                lyrics.push(LyricsEntry {
+                    text: timed.to_lrc(LrcPrecision::Milliseconds),
+                    ..entry
+                })
             }
         }
-        let lyrics = tag.lyrics().next().map(|s| s.text.to_owned());
+        let text = |id: &str| {
+            tag.get(id)
+                .and_then(|frame| frame.content().text())
+                .map(|s| s.to_string())
+        };
+        let comment = tag
+            .comments()
+            .find(|c| c.description.is_empty())
+            .map(|c| c.text.to_owned());
         Ok(MusicTag {
             path,
             fmt: MusicFormat::Mp3,
@@ -104,8 +349,25 @@ impl TryFrom<(id3::Tag, Option<PathBuf>)> for MusicTag {
             album: tag.album().map(|s| s.to_string()),
             album_artists,
             year: tag.year(),
+            track_number: tag.track(),
+            track_total: tag.total_tracks(),
+            disc_number: tag.disc(),
+            disc_total: tag.total_discs(),
+            genre: tag.genre().map(parse_id3_genre),
+            composer: text("TCOM"),
+            lyricist: text("TEXT"),
+            conductor: text("TPE3"),
+            comment,
+            copyright: text("TCOP"),
+            publisher: text("TPUB"),
+            grouping: text("TIT1"),
+            bpm: text("TBPM").and_then(|bpm| parse_bpm(&bpm)),
+            encoder: text("TSSE"),
             artworks,
             lyrics,
+            properties: None,
+            #[cfg(feature = "image")]
+            artwork_policy: None,
         })
     }
 }
@@ -120,6 +382,25 @@ impl TryFrom<(metaflac::Tag, Option<PathBuf>)> for MusicTag {
                 .map(|s| s.into())
         }
         let (tag, path) = value;
+        let mut lyrics_fields: Vec<_> = tag
+            .vorbis_comments()
+            .into_iter()
+            .flat_map(|comments| &comments.comments)
+            .filter_map(|(key, values)| Some((vorbis_lyrics_lang(key)?, key, values)))
+            .collect();
+        // Comments are a map, so sort by language, entries without one
+        // first, then by key.
+        lyrics_fields.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+        let lyrics = lyrics_fields
+            .into_iter()
+            .flat_map(|(lang, _, values)| {
+                values.iter().map(move |text| LyricsEntry {
+                    text: text.to_owned(),
+                    lang: lang.clone(),
+                    description: String::new(),
+                })
+            })
+            .collect();
         let mut artists = Vec::new();
         if let Some(artist_iter) = tag.get_vorbis("ARTIST") {
             for artist in artist_iter {
@@ -134,17 +415,29 @@ impl TryFrom<(metaflac::Tag, Option<PathBuf>)> for MusicTag {
         };
         let mut artworks = Vec::new();
         for pic in tag.pictures() {
-            if let metaflac::block::PictureType::CoverFront = pic.picture_type {
-                if let Ok(size) = imagesize::blob_size(&pic.data) {
-                    artworks.push(Artwork {
-                        data: pic.data.to_vec(),
-                        height: size.height,
-                        width: size.width,
-                        fmt: ImgFmt::from_mime(&pic.mime_type),
-                    })
-                }
+            if let Ok(size) = imagesize::blob_size(&pic.data) {
+                artworks.push(Artwork {
+                    data: pic.data.to_vec(),
+                    height: size.height,
+                    width: size.width,
+                    fmt: ImgFmt::detect(&pic.data),
+                    picture_type: pic.picture_type.into(),
+                    description: pic.description.clone(),
+                })
             }
         }
+        let (track_number, track_total) =
+            get("TRACKNUMBER", &tag).map_or((None, None), |s| parse_number_pair(&s));
+        let track_total = get("TRACKTOTAL", &tag)
+            .or_else(|| get("TOTALTRACKS", &tag))
+            .and_then(|s| s.trim().parse().ok())
+            .or(track_total);
+        let (disc_number, disc_total) =
+            get("DISCNUMBER", &tag).map_or((None, None), |s| parse_number_pair(&s));
+        let disc_total = get("DISCTOTAL", &tag)
+            .or_else(|| get("TOTALDISCS", &tag))
+            .and_then(|s| s.trim().parse().ok())
+            .or(disc_total);
         Ok(MusicTag {
             path,
             fmt: MusicFormat::Flac,
@@ -153,8 +446,25 @@ impl TryFrom<(metaflac::Tag, Option<PathBuf>)> for MusicTag {
             album: get("ALBUM", &tag),
             album_artists,
             year: get("DATE", &tag).and_then(|year| year.parse().ok()),
+            track_number,
+            track_total,
+            disc_number,
+            disc_total,
+            genre: get("GENRE", &tag),
+            composer: get("COMPOSER", &tag),
+            lyricist: get("LYRICIST", &tag),
+            conductor: get("CONDUCTOR", &tag),
+            comment: VORBIS_COMMENT_KEYS.iter().find_map(|key| get(key, &tag)),
+            copyright: get("COPYRIGHT", &tag),
+            publisher: VORBIS_PUBLISHER_KEYS.iter().find_map(|key| get(key, &tag)),
+            grouping: get("GROUPING", &tag),
+            bpm: get("BPM", &tag).and_then(|bpm| parse_bpm(&bpm)),
+            encoder: get("ENCODER", &tag),
             artworks,
-            lyrics: get("LYRICS", &tag),
+            lyrics,
+            properties: None,
+            #[cfg(feature = "image")]
+            artwork_policy: None,
         })
     }
 }
@@ -168,21 +478,23 @@ impl TryFrom<(mp4ameta::Tag, Option<PathBuf>)> for MusicTag {
         let album_artists = split_artists_to_string(tag.album_artists());
         let mut artworks = Vec::new();
         for img in tag.artworks() {
-            let fmt = if let mp4ameta::ImgFmt::Png = img.fmt {
-                ImgFmt::PNG
-            } else {
-                ImgFmt::JPEG
-            };
             if let Ok(size) = imagesize::blob_size(img.data) {
                 artworks.push(Artwork {
                     height: size.height,
                     width: size.width,
                     data: img.data.to_owned(),
-                    fmt,
+                    fmt: ImgFmt::detect(img.data),
+                    // MP4 covers carry no type or description.
+                    picture_type: PictureType::CoverFront,
+                    description: String::new(),
                 })
             }
         }
-        let lyrics = tag.lyrics().map(|s| s.into());
+        let lyrics = tag
+            .strings_of(&mp4ameta::ident::LYRICS)
+            .map(LyricsEntry::new)
+            .collect();
+        let freeform = |ident| tag.strings_of(&ident).next().map(|s| s.to_string());
         Ok(MusicTag {
             path,
             fmt: MusicFormat::M4a,
@@ -191,8 +503,25 @@ impl TryFrom<(mp4ameta::Tag, Option<PathBuf>)> for MusicTag {
             album: tag.album().map(|s| s.to_string()),
             album_artists,
             year: tag.year().and_then(|year| year.parse().ok()),
+            track_number: tag.track_number().map(u32::from),
+            track_total: tag.total_tracks().map(u32::from),
+            disc_number: tag.disc_number().map(u32::from),
+            disc_total: tag.total_discs().map(u32::from),
+            genre: tag.genre().map(|s| s.to_string()),
+            composer: tag.composer().map(|s| s.to_string()),
+            lyricist: tag.lyricist().map(|s| s.to_string()),
+            conductor: freeform(MP4_CONDUCTOR),
+            comment: tag.comment().map(|s| s.to_string()),
+            copyright: tag.copyright().map(|s| s.to_string()),
+            publisher: freeform(MP4_LABEL),
+            grouping: tag.grouping().map(|s| s.to_string()),
+            bpm: tag.bpm().map(u32::from),
+            encoder: tag.encoder().map(|s| s.to_string()),
             artworks,
             lyrics,
+            properties: None,
+            #[cfg(feature = "image")]
+            artwork_policy: None,
         })
     }
 }
@@ -201,14 +530,35 @@ impl TryFrom<(Box<dyn FormatReader>, Option<PathBuf>)> for MusicTag {
     type Error = crate::Error;
 
     fn try_from(value: (Box<dyn FormatReader>, Option<PathBuf>)) -> Result<Self, Self::Error> {
-        use symphonia::core::meta::Value;
+        use symphonia::core::meta::{StandardTagKey, Value};
         let mut info = default_info(MusicFormat::Ogg);
         let (mut reader, path) = value;
         info.path = path;
         if let Some(current) = reader.metadata().current() {
+            // Symphonia decodes METADATA_BLOCK_PICTURE comments into visuals.
+            for visual in current.visuals() {
+                if let Ok(size) = imagesize::blob_size(&visual.data) {
+                    info.artworks.push(Artwork {
+                        height: size.height,
+                        width: size.width,
+                        data: visual.data.to_vec(),
+                        fmt: ImgFmt::detect(&visual.data),
+                        picture_type: visual.usage.into(),
+                        description: visual
+                            .tags
+                            .iter()
+                            .find(|tag| tag.std_key == Some(StandardTagKey::Description))
+                            .map(|tag| tag.value.to_string())
+                            .unwrap_or_default(),
+                    })
+                }
+            }
+            let mut cover_art = None;
+            let mut aliases = Vec::new();
             for tag in current.tags() {
                 match &tag.value {
-                    Value::String(value) => match tag.key.as_str() {
+                    // Field names are case-insensitive.
+                    Value::String(value) => match tag.key.to_ascii_uppercase().as_str() {
                         "TITLE" => info.title = Some(value.into()),
                         "ALBUM" => info.album = Some(value.into()),
                         "ALBUMARTIST" => info
@@ -216,89 +566,166 @@ impl TryFrom<(Box<dyn FormatReader>, Option<PathBuf>)> for MusicTag {
                             .append(&mut split_artist_to_string(value)),
                         "ARTIST" => info.artists.append(&mut split_artist_to_string(value)),
                         "DATE" => info.year = value.parse().ok(),
-                        "LYRICS" => info.lyrics = Some(value.into()),
-                        "METADATA_BLOCK_PICTURE" => {
-                            if let Ok(pic) = base64::prelude::BASE64_STANDARD.decode(value) {
-                                if let Ok(size) = imagesize::blob_size(&pic[42..]) {
-                                    let image = Artwork {
-                                        fmt: ImgFmt::JPEG,
-                                        data: pic[42..].to_owned(),
-                                        width: size.width,
-                                        height: size.height,
-                                    };
-                                    info.artworks.push(image);
-                                } else if let Ok(size) = imagesize::blob_size(&pic[41..]) {
-                                    let image = Artwork {
-                                        fmt: ImgFmt::JPEG,
-                                        data: pic[41..].to_owned(),
-                                        width: size.width,
-                                        height: size.height,
-                                    };
-                                    info.artworks.push(image);
-                                }
-                            }
+                        "TRACKNUMBER" => {
+                            let (number, total) = parse_number_pair(value);
+                            info.track_number = number;
+                            info.track_total = info.track_total.or(total);
+                        }
+                        "TRACKTOTAL" | "TOTALTRACKS" => {
+                            info.track_total = value.trim().parse().ok()
                         }
-                        _ => (),
+                        "DISCNUMBER" => {
+                            let (number, total) = parse_number_pair(value);
+                            info.disc_number = number;
+                            info.disc_total = info.disc_total.or(total);
+                        }
+                        "DISCTOTAL" | "TOTALDISCS" => info.disc_total = value.trim().parse().ok(),
+                        "GENRE" => info.genre = Some(value.into()),
+                        "COMPOSER" => info.composer = Some(value.into()),
+                        "LYRICIST" => info.lyricist = Some(value.into()),
+                        "CONDUCTOR" => info.conductor = Some(value.into()),
+                        "COMMENT" => info.comment = Some(value.into()),
+                        "COPYRIGHT" => info.copyright = Some(value.into()),
+                        "LABEL" => info.publisher = Some(value.into()),
+                        "GROUPING" => info.grouping = Some(value.into()),
+                        "BPM" => info.bpm = parse_bpm(value),
+                        "ENCODER" => info.encoder = Some(value.into()),
+                        key if vorbis_lyrics_lang(key).is_some() => info.lyrics.push(LyricsEntry {
+                            text: value.into(),
+                            lang: vorbis_lyrics_lang(key).flatten(),
+                            description: String::new(),
+                        }),
+                        "COVERART" => cover_art = Some(value),
+                        key => aliases.push((key.to_owned(), value)),
                     },
                     _ => continue,
                 }
             }
+            // Aliases only fill fields left empty, tried in the same order as
+            // for FLAC.
+            let alias = |keys: &[&str]| {
+                keys[1..].iter().find_map(|alias| {
+                    aliases
+                        .iter()
+                        .find(|(key, _)| key == alias)
+                        .map(|(_, value)| value.to_string())
+                })
+            };
+            info.comment = info.comment.take().or_else(|| alias(&VORBIS_COMMENT_KEYS));
+            info.publisher = info
+                .publisher
+                .take()
+                .or_else(|| alias(&VORBIS_PUBLISHER_KEYS));
+            // The legacy field holds the bare image in base64, next to a
+            // COVERARTMIME field that detecting the format makes redundant.
+            if let Some(data) = cover_art.and_then(|value| BASE64_STANDARD.decode(value).ok()) {
+                if let Ok(size) = imagesize::blob_size(&data) {
+                    info.artworks.push(Artwork {
+                        height: size.height,
+                        width: size.width,
+                        fmt: ImgFmt::detect(&data),
+                        data,
+                        picture_type: PictureType::CoverFront,
+                        description: String::new(),
+                    })
+                }
+            }
         }
         Ok(info)
     }
 }
+pub(super) fn probe_format(
+    src: Box<dyn MediaSource>,
+    ext: &str,
+) -> crate::Result<Box<dyn FormatReader>> {
+    let mss = MediaSourceStream::new(src, Default::default());
+    let mut hint = Hint::new();
+    hint.with_extension(ext);
+    let probed = symphonia::default::get_probe().format(
+        &hint,
+        mss,
+        &Default::default(),
+        &Default::default(),
+    )?;
+    Ok(probed.format)
+}
+
 impl MusicTag {
-    pub fn read_from_bytes(bytes: impl Into<Vec<u8>>, fmt: MusicFormat) -> crate::Result<Self> {
-        let reader = AudioReader::new(bytes.into());
-        match fmt {
+    /// Reads the tag from an in-memory file. The format is detected from
+    /// the content; `hint` is only used when that fails.
+    pub fn read_from_bytes(bytes: impl Into<Vec<u8>>, hint: MusicFormat) -> crate::Result<Self> {
+        let mut reader = AudioReader::new(bytes.into());
+        let fmt = MusicFormat::detect_from_reader(&mut reader)?.unwrap_or(hint);
+        let properties = AudioProperties::read_from(&mut reader, fmt).ok();
+        reader.seek(SeekFrom::Start(0))?;
+        let mut info = match fmt {
             MusicFormat::Mp3 => {
                 use id3::Tag;
-                let tag = Tag::read_from(reader)?;
+                let tag = Tag::read_from2(reader)?;
                 Self::try_from((tag, None))
             }
-            MusicFormat::Flac => todo!(),
-            _ => todo!(),
-        }
+            MusicFormat::Flac => {
+                use metaflac::Tag;
+                skip_id3v2(&mut reader)?;
+                let tag = Tag::read_from(&mut reader)?;
+                Self::try_from((tag, None))
+            }
+            MusicFormat::M4a => {
+                use mp4ameta::Tag;
+                let tag = Tag::read_from(&mut reader)?;
+                Self::try_from((tag, None))
+            }
+            MusicFormat::Ogg => {
+                let format = probe_format(Box::new(reader), "ogg")?;
+                Self::try_from((format, None))
+            }
+        }?;
+        info.properties = properties;
+        Ok(info)
     }
+    /// Reads the tag of the file at `path`. The format is detected from the
+    /// content; the extension is only used when that fails.
     pub fn read_from_path(path: impl AsRef<Path>) -> crate::Result<Self> {
         let path = path.as_ref().to_path_buf();
-        let ext = match path.extension().and_then(|f| f.to_str()) {
-            Some(ext) => ext,
-            _ => return Err(crate::error::Error::FmtError("Not Supported".into())),
+        let mut file = File::open(&path)?;
+        let fmt = match MusicFormat::detect_from_reader(&mut file)? {
+            Some(fmt) => fmt,
+            None => {
+                let ext = match path.extension().and_then(|f| f.to_str()) {
+                    Some(ext) => ext,
+                    _ => return Err(crate::error::Error::FmtError("Not Supported".into())),
+                };
+                MusicFormat::from_extension(ext)
+                    .ok_or_else(|| crate::Error::FmtError(ext.into()))?
+            }
         };
-        match ext {
-            "mp3" => {
+        let properties = AudioProperties::read_from(&mut BufReader::new(&mut file), fmt).ok();
+        file.seek(SeekFrom::Start(0))?;
+        let mut info = match fmt {
+            MusicFormat::Mp3 => {
                 use id3::Tag;
 
-                let tag = Tag::read_from_path(&path)?;
+                let tag = Tag::read_from2(file)?;
                 Self::try_from((tag, Some(path)))
             }
-            "flac" => {
+            MusicFormat::Flac => {
                 use metaflac::Tag;
-                let tag = Tag::read_from_path(&path)?;
+                skip_id3v2(&mut file)?;
+                let tag = Tag::read_from(&mut BufReader::new(file))?;
                 Self::try_from((tag, Some(path)))
             }
-            "m4a" => {
+            MusicFormat::M4a => {
                 use mp4ameta::Tag;
-                let tag = Tag::read_from_path(&path)?;
+                let tag = Tag::read_from(&mut BufReader::new(file))?;
                 Self::try_from((tag, Some(path)))
             }
-            "ogg" => {
-                let src = File::open(&path)?;
-                let mss = MediaSourceStream::new(Box::new(src), Default::default());
-                let mut hint = Hint::new();
-                hint.with_extension("ogg");
-                let probed = symphonia::default::get_probe().format(
-                    &hint,
-                    mss,
-                    &Default::default(),
-                    &Default::default(),
-                )?;
-                let format = probed.format;
+            MusicFormat::Ogg => {
+                let format = probe_format(Box::new(file), "ogg")?;
                 Self::try_from((format, Some(path)))
             }
-            e => Err(crate::Error::FmtError(e.into())),
-        }
+        }?;
+        info.properties = properties;
+        Ok(info)
     }
 
     pub fn as_path(&self) -> Option<&Path> {
@@ -307,6 +734,11 @@ impl MusicTag {
     pub fn fmt(&self) -> MusicFormat {
         self.fmt
     }
+    /// Properties of the audio stream. Only available when the stream could
+    /// be parsed while reading the tag.
+    pub fn properties(&self) -> Option<&AudioProperties> {
+        self.properties.as_ref()
+    }
     pub fn title(&self) -> Option<&str> {
         self.title.as_deref()
     }
@@ -336,7 +768,7 @@ impl MusicTag {
         self.album_artists.iter().map(|s| s.as_str())
     }
     pub fn set_album_artists(&mut self, album_artists: Vec<impl Into<String>>) {
-        self.artists = album_artists.into_iter().map(|s| s.into()).collect();
+        self.album_artists = album_artists.into_iter().map(|s| s.into()).collect();
     }
     pub fn year(&self) -> Option<i32> {
         self.year
@@ -344,18 +776,138 @@ impl MusicTag {
     pub fn set_year(&mut self, year: i32) {
         self.year = Some(year);
     }
+    pub fn track_number(&self) -> Option<u32> {
+        self.track_number
+    }
+    pub fn set_track_number(&mut self, track_number: u32) {
+        self.track_number = Some(track_number);
+    }
+    pub fn track_total(&self) -> Option<u32> {
+        self.track_total
+    }
+    pub fn set_track_total(&mut self, track_total: u32) {
+        self.track_total = Some(track_total);
+    }
+    pub fn disc_number(&self) -> Option<u32> {
+        self.disc_number
+    }
+    pub fn set_disc_number(&mut self, disc_number: u32) {
+        self.disc_number = Some(disc_number);
+    }
+    pub fn disc_total(&self) -> Option<u32> {
+        self.disc_total
+    }
+    pub fn set_disc_total(&mut self, disc_total: u32) {
+        self.disc_total = Some(disc_total);
+    }
+    pub fn genre(&self) -> Option<&str> {
+        self.genre.as_deref()
+    }
+    pub fn set_genre(&mut self, genre: impl Into<String>) {
+        self.genre = Some(genre.into());
+    }
+    pub fn composer(&self) -> Option<&str> {
+        self.composer.as_deref()
+    }
+    pub fn set_composer(&mut self, composer: impl Into<String>) {
+        self.composer = Some(composer.into());
+    }
+    pub fn lyricist(&self) -> Option<&str> {
+        self.lyricist.as_deref()
+    }
+    pub fn set_lyricist(&mut self, lyricist: impl Into<String>) {
+        self.lyricist = Some(lyricist.into());
+    }
+    pub fn conductor(&self) -> Option<&str> {
+        self.conductor.as_deref()
+    }
+    pub fn set_conductor(&mut self, conductor: impl Into<String>) {
+        self.conductor = Some(conductor.into());
+    }
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+    pub fn set_comment(&mut self, comment: impl Into<String>) {
+        self.comment = Some(comment.into());
+    }
+    pub fn copyright(&self) -> Option<&str> {
+        self.copyright.as_deref()
+    }
+    pub fn set_copyright(&mut self, copyright: impl Into<String>) {
+        self.copyright = Some(copyright.into());
+    }
+    pub fn publisher(&self) -> Option<&str> {
+        self.publisher.as_deref()
+    }
+    pub fn set_publisher(&mut self, publisher: impl Into<String>) {
+        self.publisher = Some(publisher.into());
+    }
+    pub fn grouping(&self) -> Option<&str> {
+        self.grouping.as_deref()
+    }
+    pub fn set_grouping(&mut self, grouping: impl Into<String>) {
+        self.grouping = Some(grouping.into());
+    }
+    pub fn bpm(&self) -> Option<u32> {
+        self.bpm
+    }
+    pub fn set_bpm(&mut self, bpm: u32) {
+        self.bpm = Some(bpm);
+    }
+    pub fn encoder(&self) -> Option<&str> {
+        self.encoder.as_deref()
+    }
+    pub fn set_encoder(&mut self, encoder: impl Into<String>) {
+        self.encoder = Some(encoder.into());
+    }
+    /// The first lyrics entry.
     pub fn lyrics(&self) -> Option<Lyrics> {
-        self.lyrics.as_ref().map(Lyrics::from)
+        self.lyrics.first().map(LyricsEntry::lyrics)
+    }
+    pub fn lyrics_entries(&self) -> &[LyricsEntry] {
+        &self.lyrics
+    }
+    /// The first lyrics entry in `lang`. ISO-639-1 and ISO-639-2 codes of a
+    /// language match each other, so `eng` finds lyrics stored as `en`.
+    pub fn lyrics_in(&self, lang: &str) -> Option<Lyrics> {
+        self.lyrics
+            .iter()
+            .find(|entry| {
+                entry
+                    .lang
+                    .as_deref()
+                    .is_some_and(|l| same_language(l, lang))
+            })
+            .map(LyricsEntry::lyrics)
+    }
+    /// Replaces all lyrics with `lyrics`, LRC or plain text. Timed lyrics are
+    /// also written to MP3s as a SYLT frame.
+    pub fn set_lyrics(&mut self, lyrics: impl Into<String>) {
+        self.lyrics = vec![LyricsEntry::new(lyrics)];
     }
+    pub fn add_lyrics(&mut self, entry: LyricsEntry) {
+        self.lyrics.push(entry);
+    }
+    pub fn set_lyrics_entries(&mut self, entries: Vec<LyricsEntry>) {
+        self.lyrics = entries;
+    }
+    /// The front cover, or the first picture when there is none.
     pub fn artwork(&self) -> Option<&Artwork> {
-        self.artworks.first()
+        self.artworks
+            .iter()
+            .find(|artwork| artwork.picture_type == PictureType::CoverFront)
+            .or(self.artworks.first())
     }
     pub fn artworks(&self) -> impl Iterator<Item = &Artwork> {
         self.artworks.iter()
     }
+    /// Adds a picture. MP3 tags hold one picture per [`PictureType`], so
+    /// writing two of the same type to an MP3 fails.
     pub fn add_artwork(&mut self, artwork: Artwork) {
         self.artworks.push(artwork)
     }
+    /// Replaces all pictures with front covers. MP3 tags hold one picture
+    /// per [`PictureType`], so writing more than one to an MP3 fails.
     pub fn set_artworks(
         &mut self,
         artworks: Vec<(impl Into<Vec<u8>>, ImgFmt)>,
@@ -369,18 +921,45 @@ impl MusicTag {
                 width: size.width,
                 data,
                 fmt,
+                picture_type: PictureType::CoverFront,
+                description: String::new(),
             });
         }
         self.artworks = pics;
         Ok(())
     }
 
+    /// Artworks are brought within the limits of `policy` whenever the tag
+    /// is written; the artworks of the tag itself stay as they are.
+    #[cfg(feature = "image")]
+    pub fn set_artwork_policy(&mut self, policy: Option<super::ArtworkPolicy>) {
+        self.artwork_policy = policy;
+    }
+    #[cfg(feature = "image")]
+    pub fn artwork_policy(&self) -> Option<&super::ArtworkPolicy> {
+        self.artwork_policy.as_ref()
+    }
+
     pub fn write_to_path(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        #[cfg(feature = "image")]
+        let normalized = match &self.artwork_policy {
+            Some(policy) => Some(
+                self.artworks
+                    .iter()
+                    .map(|artwork| policy.apply(artwork))
+                    .collect::<crate::Result<Vec<_>>>()?,
+            ),
+            None => None,
+        };
+        #[cfg(feature = "image")]
+        let artworks = normalized.as_deref().unwrap_or(&self.artworks);
+        #[cfg(not(feature = "image"))]
+        let artworks = &self.artworks;
         match self.fmt {
-            MusicFormat::Mp3 => write_to_path_mp3(self, path),
-            MusicFormat::Flac => write_to_path_flac(self, path),
-            MusicFormat::M4a => write_to_path_m4a(self, path),
-            _ => Err(crate::Error::NotSupportedError),
+            MusicFormat::Mp3 => write_to_path_mp3(self, artworks, path),
+            MusicFormat::Flac => write_to_path_flac(self, artworks, path),
+            MusicFormat::M4a => write_to_path_m4a(self, artworks, path),
+            MusicFormat::Ogg => write_to_path_ogg(self, artworks, path),
         }
     }
 }
@@ -389,7 +968,11 @@ fn generate_artist(artists: &[String]) -> String {
     artists.iter().map(|s| format!("{}/", s)).collect()
 }
 
-fn write_to_path_m4a(info: &MusicTag, path: impl AsRef<Path>) -> crate::Result<()> {
+fn write_to_path_m4a(
+    info: &MusicTag,
+    artworks: &[Artwork],
+    path: impl AsRef<Path>,
+) -> crate::Result<()> {
     use mp4ameta::Tag;
     let mut tag = Tag::read_from_path(path.as_ref())?;
 
@@ -413,24 +996,127 @@ fn write_to_path_m4a(info: &MusicTag, path: impl AsRef<Path>) -> crate::Result<(
     if !info.album_artists.is_empty() {
         tag.set_album_artists(info.album_artists().map(|s| s.to_string()))
     }
+    fn to_u16(n: u32) -> u16 {
+        u16::try_from(n).unwrap_or(u16::MAX)
+    }
+    tag.remove_track_number();
+    if let Some(number) = info.track_number {
+        tag.set_track_number(to_u16(number))
+    }
+    tag.remove_total_tracks();
+    // A number of 0 in `trkn` and `disk` means absent, so totals can be
+    // stored on their own.
+    if let Some(total) = info.track_total {
+        tag.set_total_tracks(to_u16(total))
+    }
+    tag.remove_disc_number();
+    if let Some(number) = info.disc_number {
+        tag.set_disc_number(to_u16(number))
+    }
+    tag.remove_total_discs();
+    if let Some(total) = info.disc_total {
+        tag.set_total_discs(to_u16(total))
+    }
+    tag.remove_genres();
+    if let Some(genre) = info.genre() {
+        tag.set_genre(genre)
+    }
+    tag.remove_composers();
+    if let Some(composer) = info.composer() {
+        tag.set_composer(composer)
+    }
+    tag.remove_lyricists();
+    if let Some(lyricist) = info.lyricist() {
+        tag.set_lyricist(lyricist)
+    }
+    tag.remove_comments();
+    if let Some(comment) = info.comment() {
+        tag.set_comment(comment)
+    }
+    tag.remove_copyright();
+    if let Some(copyright) = info.copyright() {
+        tag.set_copyright(copyright)
+    }
+    tag.remove_groupings();
+    if let Some(grouping) = info.grouping() {
+        tag.set_grouping(grouping)
+    }
+    tag.remove_bpm();
+    if let Some(bpm) = info.bpm {
+        tag.set_bpm(to_u16(bpm))
+    }
+    tag.remove_encoder();
+    if let Some(encoder) = info.encoder() {
+        tag.set_encoder(encoder)
+    }
+    use mp4ameta::Data;
+    for (ident, value) in [
+        (MP4_CONDUCTOR, info.conductor()),
+        (MP4_LABEL, info.publisher()),
+    ] {
+        tag.remove_data_of(&ident);
+        if let Some(value) = value {
+            tag.set_data(ident, Data::Utf8(value.into()))
+        }
+    }
+    tag.set_all_data(
+        mp4ameta::ident::LYRICS,
+        info.lyrics
+            .iter()
+            .map(|entry| Data::Utf8(entry.text.clone())),
+    );
     use mp4ameta::Img;
     tag.remove_artworks();
-    for artwork in info.artworks() {
-        let fmt = match artwork.fmt {
-            ImgFmt::JPEG => mp4ameta::ImgFmt::Jpeg,
-            ImgFmt::PNG => mp4ameta::ImgFmt::Png,
+    for artwork in artworks {
+        let img = match artwork.fmt.to_mp4() {
+            Some(fmt) => Img {
+                fmt,
+                data: artwork.data.clone(),
+            },
+            None => mp4_artwork(artwork)?,
         };
-        tag.add_artwork(Img {
-            fmt,
-            data: artwork.data.clone(),
-        })
+        tag.add_artwork(img)
     }
     tag.write_to_path(path)?;
     Ok(())
 }
 
-fn write_to_path_flac(info: &MusicTag, path: impl AsRef<Path>) -> crate::Result<()> {
+/// MP4 stores JPEG, PNG and BMP covers only; other images become PNG.
+#[cfg(feature = "image")]
+fn mp4_artwork(artwork: &Artwork) -> crate::Result<mp4ameta::Img<Vec<u8>>> {
+    let mut artwork = artwork.clone();
+    artwork.to_png()?;
+    Ok(mp4ameta::Img {
+        fmt: mp4ameta::ImgFmt::Png,
+        data: artwork.data,
+    })
+}
+
+/// MP4 stores JPEG, PNG and BMP covers only; converting others needs the
+/// `image` feature.
+#[cfg(not(feature = "image"))]
+fn mp4_artwork(artwork: &Artwork) -> crate::Result<mp4ameta::Img<Vec<u8>>> {
+    Err(crate::Error::FmtError(format!(
+        "MP4 cannot store {:?} artwork without the image feature",
+        artwork.fmt
+    )))
+}
+
+fn write_to_path_flac(
+    info: &MusicTag,
+    artworks: &[Artwork],
+    path: impl AsRef<Path>,
+) -> crate::Result<()> {
     use metaflac::Tag;
+    // metaflac would write its blocks in front of the ID3v2 tag, leaving the
+    // old ones behind it.
+    let mut header = [0; 3];
+    File::open(path.as_ref())?.read_exact(&mut header)?;
+    if &header == b"ID3" {
+        return Err(crate::Error::FmtError(
+            "Cannot write a FLAC file starting with an ID3v2 tag".into(),
+        ));
+    }
     let mut tag = Tag::read_from_path(path.as_ref())?;
     fn set(key: &str, value: Option<impl Into<String>>, tag: &mut Tag) {
         tag.remove_vorbis(key);
@@ -440,27 +1126,54 @@ fn write_to_path_flac(info: &MusicTag, path: impl AsRef<Path>) -> crate::Result<
     }
     set("TITLE", info.title(), &mut tag);
     set("ALBUM", info.album(), &mut tag);
-    set("LYRICS", info.lyrics.as_deref(), &mut tag);
-    let artists: Option<String> = if info.artists.is_empty() {
-        Some(info.artists().map(|s| format!("{}/", s)).collect())
-    } else {
-        None
-    };
-    let album_artists: Option<String> = if info.album_artists.is_empty() {
-        Some(info.artists().map(|s| format!("{}/", s)).collect())
-    } else {
-        None
-    };
+    let comments = &mut tag.vorbis_comments_mut().comments;
+    comments.retain(|key, _| vorbis_lyrics_lang(key).is_none());
+    for entry in &info.lyrics {
+        let values = comments.entry(vorbis_lyrics_key(entry)).or_default();
+        values.push(entry.text.clone());
+    }
+    let artists = (!info.artists.is_empty()).then(|| generate_artist(&info.artists));
+    let album_artists =
+        (!info.album_artists.is_empty()).then(|| generate_artist(&info.album_artists));
     set("ARTIST", artists, &mut tag);
     set("ALBUMARTIST", album_artists, &mut tag);
     set("DATE", info.year.map(|year| year.to_string()), &mut tag);
-    use metaflac::block::{Picture, PictureType};
+    let number = |n: Option<u32>| n.map(|n| n.to_string());
+    set("TRACKNUMBER", number(info.track_number), &mut tag);
+    set("TRACKTOTAL", number(info.track_total), &mut tag);
+    set("DISCNUMBER", number(info.disc_number), &mut tag);
+    set("DISCTOTAL", number(info.disc_total), &mut tag);
+    tag.remove_vorbis("TOTALTRACKS");
+    tag.remove_vorbis("TOTALDISCS");
+    set("GENRE", info.genre(), &mut tag);
+    set("COMPOSER", info.composer(), &mut tag);
+    set("LYRICIST", info.lyricist(), &mut tag);
+    set("CONDUCTOR", info.conductor(), &mut tag);
+    let present = |key: &str| tag.get_vorbis(key).is_some();
+    let stale = [
+        stale_aliases(&VORBIS_COMMENT_KEYS, info.comment.is_none(), present),
+        stale_aliases(&VORBIS_PUBLISHER_KEYS, info.publisher.is_none(), present),
+    ]
+    .concat();
+    for key in stale {
+        tag.remove_vorbis(key);
+    }
+    set("COMMENT", info.comment(), &mut tag);
+    set("COPYRIGHT", info.copyright(), &mut tag);
+    set("LABEL", info.publisher(), &mut tag);
+    set("GROUPING", info.grouping(), &mut tag);
+    set("BPM", number(info.bpm), &mut tag);
+    set("ENCODER", info.encoder(), &mut tag);
+    use metaflac::block::{BlockType, Picture};
     use metaflac::Block;
-    tag.remove_picture_type(PictureType::CoverFront);
-    for artwork in info.artworks() {
+    tag.remove_blocks(BlockType::Picture);
+    for artwork in artworks {
         let pic = Picture {
-            picture_type: PictureType::CoverFront,
+            picture_type: artwork.picture_type.into(),
             mime_type: artwork.mime_type().into(),
+            description: artwork.description.clone(),
+            width: artwork.width as u32,
+            height: artwork.height as u32,
             data: artwork.data.clone(),
             ..Default::default()
         };
@@ -470,8 +1183,99 @@ fn write_to_path_flac(info: &MusicTag, path: impl AsRef<Path>) -> crate::Result<
     Ok(())
 }
 
-fn write_to_path_mp3(info: &MusicTag, path: impl AsRef<Path>) -> crate::Result<()> {
+fn write_to_path_ogg(
+    info: &MusicTag,
+    artworks: &[Artwork],
+    path: impl AsRef<Path>,
+) -> crate::Result<()> {
+    use super::ogg::OggFile;
+    let mut file = OggFile::read_from(&std::fs::read(path.as_ref())?)?;
+    let tag = &mut file.comments;
+    fn set(key: &str, value: Option<impl Into<String>>, tag: &mut VorbisComments) {
+        tag.remove(key);
+        if let Some(value) = value {
+            tag.push(key, value)
+        }
+    }
+    set("TITLE", info.title(), tag);
+    set("ALBUM", info.album(), tag);
+    tag.comments
+        .retain(|(key, _)| vorbis_lyrics_lang(key).is_none());
+    for entry in &info.lyrics {
+        tag.push(&vorbis_lyrics_key(entry), entry.text.clone());
+    }
+    let artists = (!info.artists.is_empty()).then(|| generate_artist(&info.artists));
+    let album_artists =
+        (!info.album_artists.is_empty()).then(|| generate_artist(&info.album_artists));
+    set("ARTIST", artists, tag);
+    set("ALBUMARTIST", album_artists, tag);
+    set("DATE", info.year.map(|year| year.to_string()), tag);
+    let number = |n: Option<u32>| n.map(|n| n.to_string());
+    set("TRACKNUMBER", number(info.track_number), tag);
+    set("TRACKTOTAL", number(info.track_total), tag);
+    set("DISCNUMBER", number(info.disc_number), tag);
+    set("DISCTOTAL", number(info.disc_total), tag);
+    tag.remove("TOTALTRACKS");
+    tag.remove("TOTALDISCS");
+    set("GENRE", info.genre(), tag);
+    set("COMPOSER", info.composer(), tag);
+    set("LYRICIST", info.lyricist(), tag);
+    set("CONDUCTOR", info.conductor(), tag);
+    let present = |key: &str| tag.contains(key);
+    let stale = [
+        stale_aliases(&VORBIS_COMMENT_KEYS, info.comment.is_none(), present),
+        stale_aliases(&VORBIS_PUBLISHER_KEYS, info.publisher.is_none(), present),
+    ]
+    .concat();
+    for key in stale {
+        tag.remove(key);
+    }
+    set("COMMENT", info.comment(), tag);
+    set("COPYRIGHT", info.copyright(), tag);
+    set("LABEL", info.publisher(), tag);
+    set("GROUPING", info.grouping(), tag);
+    set("BPM", number(info.bpm), tag);
+    set("ENCODER", info.encoder(), tag);
+    use metaflac::block::Picture;
+    tag.remove("METADATA_BLOCK_PICTURE");
+    tag.remove("COVERART");
+    tag.remove("COVERARTMIME");
+    for artwork in artworks {
+        let pic = Picture {
+            picture_type: artwork.picture_type.into(),
+            mime_type: artwork.mime_type().into(),
+            description: artwork.description.clone(),
+            width: artwork.width as u32,
+            height: artwork.height as u32,
+            data: artwork.data.clone(),
+            ..Default::default()
+        };
+        tag.push(
+            "METADATA_BLOCK_PICTURE",
+            BASE64_STANDARD.encode(pic.to_bytes()),
+        );
+    }
+    std::fs::write(path, file.into_bytes())?;
+    Ok(())
+}
+
+fn write_to_path_mp3(
+    info: &MusicTag,
+    artworks: &[Artwork],
+    path: impl AsRef<Path>,
+) -> crate::Result<()> {
     use id3::Tag;
+    // ID3 keeps one picture per type; a later one would replace the other.
+    let mut picture_types = HashSet::new();
+    if let Some(artwork) = artworks
+        .iter()
+        .find(|artwork| !picture_types.insert(artwork.picture_type))
+    {
+        return Err(crate::Error::FmtError(format!(
+            "MP3 tags hold one picture per type, found several of type {:?}",
+            artwork.picture_type
+        )));
+    }
     let mut tag = Tag::read_from_path(path.as_ref())?;
     if let Some(title) = info.title() {
         tag.set_title(title)
@@ -498,24 +1302,582 @@ fn write_to_path_mp3(info: &MusicTag, path: impl AsRef<Path>) -> crate::Result<(
     } else {
         tag.set_album_artist(generate_artist(&info.album_artists))
     }
-    use id3::frame::{Lyrics, Picture, PictureType};
-    tag.remove_all_lyrics();
-    if let Some(lyrics) = &info.lyrics {
-        tag.add_frame(Lyrics {
-            lang: "utf-8".into(),
+    // TRCK and TPOS hold `number/total`, so a total without its number is
+    // dropped rather than written against an invented number.
+    fn number_pair(number: Option<u32>, total: Option<u32>) -> Option<String> {
+        let number = number?;
+        Some(match total {
+            Some(total) => format!("{}/{}", number, total),
+            None => number.to_string(),
+        })
+    }
+    tag.remove("TRCK");
+    if let Some(text) = number_pair(info.track_number, info.track_total) {
+        tag.set_text("TRCK", text)
+    }
+    tag.remove("TPOS");
+    if let Some(text) = number_pair(info.disc_number, info.disc_total) {
+        tag.set_text("TPOS", text)
+    }
+    if let Some(genre) = info.genre() {
+        tag.set_genre(genre)
+    } else {
+        tag.remove_genre()
+    }
+    let bpm = info.bpm.map(|bpm| bpm.to_string());
+    for (id, value) in [
+        ("TCOM", info.composer()),
+        ("TEXT", info.lyricist()),
+        ("TPE3", info.conductor()),
+        ("TCOP", info.copyright()),
+        ("TPUB", info.publisher()),
+        ("TIT1", info.grouping()),
+        ("TBPM", bpm.as_deref()),
+        ("TSSE", info.encoder()),
+    ] {
+        match value {
+            Some(value) => tag.set_text(id, value),
+            None => {
+                tag.remove(id);
+            }
+        }
+    }
+    use id3::frame::Comment;
+    tag.remove_comment(Some(""), None);
+    if let Some(comment) = info.comment() {
+        tag.add_frame(Comment {
+            lang: "eng".into(),
             description: String::new(),
-            text: lyrics.into(),
+            text: comment.into(),
         });
     }
-    tag.remove_picture_by_type(PictureType::CoverFront);
-    for artwork in info.artworks() {
+    use id3::frame::Picture;
+    tag.remove_all_lyrics();
+    tag.remove_all_synchronised_lyrics();
+    // ID3 keeps one USLT frame per language and description, so repeated
+    // ones are numbered, and one SYLT frame per language, which only the
+    // first timed entry gets. USLT frames keep the timestamps of the others.
+    let mut descriptions = HashSet::new();
+    let mut sylt_langs = HashSet::new();
+    for entry in &info.lyrics {
+        let lang = id3_lang_code(entry.lang.as_deref().unwrap_or_default());
+        let mut description = entry.description.clone();
+        for n in 2.. {
+            if descriptions.insert((lang.clone(), description.clone())) {
+                break;
+            }
+            description = format!("{} ({})", entry.description, n).trim_start().into();
+        }
+        let lyrics = entry.lyrics();
+        if !lyrics.timed_lines().is_empty() && sylt_langs.insert(lang.clone()) {
+            tag.add_frame(lyrics.to_sylt(&lang, &description));
+        }
+        tag.add_frame(id3::frame::Lyrics {
+            lang,
+            description,
+            text: entry.text.clone(),
+        });
+    }
+    tag.remove_all_pictures();
+    for artwork in artworks {
         tag.add_frame(Picture {
             mime_type: artwork.mime_type().into(),
-            picture_type: PictureType::CoverFront,
-            description: Default::default(),
+            picture_type: artwork.picture_type.into(),
+            description: artwork.description.clone(),
             data: artwork.data.clone(),
         });
     }
     tag.write_to_path(path, tag.version())?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An ID3v2.4 tag with `len` bytes of frames, followed by `rest`.
+    fn with_id3(len: u8, rest: &[u8]) -> Vec<u8> {
+        let mut data = b"ID3\x04\x00\x00\x00\x00\x00".to_vec();
+        data.push(len);
+        data.resize(data.len() + len as usize, 0);
+        data.extend_from_slice(rest);
+        data
+    }
+
+    #[test]
+    fn detects_formats_behind_id3() {
+        let flac = with_id3(20, b"fLaC\0\0\0\x22");
+        assert_eq!(MusicFormat::detect(&flac), Some(MusicFormat::Flac));
+        let ogg = with_id3(20, b"OggS\0\x02");
+        assert_eq!(MusicFormat::detect(&ogg), Some(MusicFormat::Ogg));
+        let mp3 = with_id3(20, &[0xFF, 0xFB, 0x90, 0x64]);
+        assert_eq!(MusicFormat::detect(&mp3), Some(MusicFormat::Mp3));
+        // Only the start of the tag is known.
+        assert_eq!(MusicFormat::detect(&flac[..12]), Some(MusicFormat::Mp3));
+    }
+
+    #[test]
+    fn detects_formats_behind_id3_from_reader() {
+        let mut flac = std::io::Cursor::new(with_id3(100, b"fLaC\0\0\0\x22"));
+        let fmt = MusicFormat::detect_from_reader(&mut flac).unwrap();
+        assert_eq!(fmt, Some(MusicFormat::Flac));
+        assert_eq!(flac.position(), 0);
+        let mut unknown = std::io::Cursor::new(with_id3(100, b"junk junk junk"));
+        let fmt = MusicFormat::detect_from_reader(&mut unknown).unwrap();
+        assert_eq!(fmt, Some(MusicFormat::Mp3));
+    }
+
+    /// A few silent MPEG frames behind an ID3 tag, in a temporary file.
+    fn temp_mp3(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("music-tag-info-{}.mp3", name));
+        let mut frame = vec![0xFF, 0xFB, 0x90, 0x00];
+        frame.resize(417, 0);
+        std::fs::write(&path, frame.repeat(10)).unwrap();
+        let mut tag = id3::Tag::new();
+        tag.set_title(name);
+        tag.write_to_path(&path, id3::Version::Id3v24).unwrap();
+        path
+    }
+
+    #[test]
+    fn rewrites_ogg_tags() {
+        let mut comments = super::super::ogg::tests::comments(&[("TITLE", "Old")]);
+        comments
+            .comments
+            .push(("replaygain_track_gain".into(), "-3 dB".into()));
+        comments.comments.push(("album".into(), "Lower".into()));
+        let path = std::env::temp_dir().join("music-tag-info-rewrite.ogg");
+        std::fs::write(&path, super::super::ogg::tests::sample(&comments)).unwrap();
+
+        let mut tag = MusicTag::read_from_path(&path).unwrap();
+        assert_eq!(tag.title(), Some("Old"));
+        assert_eq!(tag.album(), Some("Lower"));
+        tag.set_title("New");
+        tag.set_genre("Rock");
+        tag.write_to_path(&path).unwrap();
+
+        let tag = MusicTag::read_from_path(&path).unwrap();
+        assert_eq!(tag.title(), Some("New"));
+        assert_eq!(tag.genre(), Some("Rock"));
+        assert_eq!(tag.album(), Some("Lower"));
+        let mut format = probe_format(Box::new(File::open(&path).unwrap()), "ogg").unwrap();
+        let metadata = format.metadata();
+        let tags = metadata.current().unwrap().tags();
+        let gain = tags.iter().find(|t| t.key == "replaygain_track_gain");
+        assert_eq!(gain.unwrap().value.to_string(), "-3 dB");
+        assert!(!tags.iter().any(|t| t.key == "REPLAYGAIN_TRACK_GAIN"));
+    }
+
+    /// An ISO base media box.
+    fn mp4_box(name: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut data = (8 + payload.len() as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(name);
+        data.extend_from_slice(payload);
+        data
+    }
+
+    #[test]
+    fn reads_flac_from_bytes() {
+        let mut tag = metaflac::Tag::new();
+        tag.set_vorbis("TITLE", vec!["Flac"]);
+        tag.set_vorbis("TRACKNUMBER", vec!["3/9"]);
+        let mut bytes = Vec::new();
+        tag.write_to(&mut bytes).unwrap();
+
+        let tag = MusicTag::read_from_bytes(bytes, MusicFormat::Mp3).unwrap();
+        assert_eq!(tag.fmt, MusicFormat::Flac);
+        assert_eq!(tag.title(), Some("Flac"));
+        assert_eq!((tag.track_number, tag.track_total), (Some(3), Some(9)));
+    }
+
+    /// An M4A file with only a title in its item list.
+    fn m4a(title: &str) -> Vec<u8> {
+        let mut data = 1u32.to_be_bytes().to_vec();
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(title.as_bytes());
+        let ilst = mp4_box(b"ilst", &mp4_box(b"\xa9nam", &mp4_box(b"data", &data)));
+        let mut meta = vec![0; 4];
+        meta.extend_from_slice(&ilst);
+        let moov = mp4_box(b"moov", &mp4_box(b"udta", &mp4_box(b"meta", &meta)));
+        let mut bytes = mp4_box(b"ftyp", b"M4A \0\0\0\0M4A isom");
+        bytes.extend_from_slice(&moov);
+        bytes
+    }
+
+    #[test]
+    fn reads_m4a_from_bytes() {
+        let tag = MusicTag::read_from_bytes(m4a("M4a"), MusicFormat::Mp3).unwrap();
+        assert_eq!(tag.fmt, MusicFormat::M4a);
+        assert_eq!(tag.title(), Some("M4a"));
+    }
+
+    #[test]
+    fn reads_ogg_from_bytes() {
+        let comments = super::super::ogg::tests::comments(&[("TITLE", "Ogg"), ("GENRE", "Jazz")]);
+        let bytes = super::super::ogg::tests::sample(&comments);
+
+        let tag = MusicTag::read_from_bytes(bytes, MusicFormat::Mp3).unwrap();
+        assert_eq!(tag.fmt, MusicFormat::Ogg);
+        assert_eq!(tag.title(), Some("Ogg"));
+        assert_eq!(tag.genre(), Some("Jazz"));
+    }
+
+    /// A 3x2 PNG.
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x03\0\0\0\x02\x08\x02\0\0\0";
+
+    /// Tags read from an Ogg Vorbis file with the given comments.
+    fn read_ogg(pairs: &[(&str, &str)]) -> MusicTag {
+        let comments = super::super::ogg::tests::comments(pairs);
+        let bytes = super::super::ogg::tests::sample(&comments);
+        MusicTag::read_from_bytes(bytes, MusicFormat::Ogg).unwrap()
+    }
+
+    #[test]
+    fn reads_ogg_picture_blocks() {
+        let picture = metaflac::block::Picture {
+            picture_type: metaflac::block::PictureType::CoverBack,
+            mime_type: "image/png".into(),
+            description: "Back".into(),
+            width: 3,
+            height: 2,
+            data: PNG.to_vec(),
+            ..Default::default()
+        };
+        let block = BASE64_STANDARD.encode(picture.to_bytes());
+        let tag = read_ogg(&[("TITLE", "Art"), ("METADATA_BLOCK_PICTURE", &block)]);
+        let artworks: Vec<_> = tag.artworks().collect();
+        let [artwork] = artworks[..] else {
+            panic!("expected one artwork, got {}", artworks.len());
+        };
+        assert_eq!(artwork.picture_type, PictureType::CoverBack);
+        assert_eq!(artwork.fmt, ImgFmt::PNG);
+        assert_eq!(artwork.mime_type(), "image/png");
+        assert_eq!(artwork.description, "Back");
+        assert_eq!((artwork.width, artwork.height), (3, 2));
+        assert_eq!(artwork.data, PNG);
+    }
+
+    #[test]
+    fn reads_legacy_ogg_cover_art() {
+        let data = BASE64_STANDARD.encode(PNG);
+        let tag = read_ogg(&[("COVERART", &data), ("COVERARTMIME", "image/png")]);
+        let artworks: Vec<_> = tag.artworks().collect();
+        let [artwork] = artworks[..] else {
+            panic!("expected one artwork, got {}", artworks.len());
+        };
+        assert_eq!(artwork.picture_type, PictureType::CoverFront);
+        assert_eq!(artwork.fmt, ImgFmt::PNG);
+        assert_eq!(artwork.data, PNG);
+    }
+
+    #[test]
+    fn skips_truncated_ogg_picture_blocks() {
+        // Shorter than the fixed fields of a picture block.
+        let block = BASE64_STANDARD.encode([0, 0, 0, 3, 0, 0, 0, 9, b'i']);
+        let tag = read_ogg(&[("TITLE", "Short"), ("METADATA_BLOCK_PICTURE", &block)]);
+        assert_eq!(tag.title(), Some("Short"));
+        assert_eq!(tag.artworks().count(), 0);
+    }
+
+    #[test]
+    fn round_trips_track_and_disc() {
+        let mut flac = Vec::new();
+        let mut tag = metaflac::Tag::new();
+        tag.set_vorbis("TITLE", vec!["Numbers"]);
+        tag.write_to(&mut flac).unwrap();
+        let ogg = super::super::ogg::tests::sample(&super::super::ogg::tests::comments(&[]));
+        let files = [
+            (temp_mp3("numbers"), None),
+            (
+                std::env::temp_dir().join("music-tag-info-numbers.flac"),
+                Some(flac),
+            ),
+            (
+                std::env::temp_dir().join("music-tag-info-numbers.m4a"),
+                Some(m4a("Numbers")),
+            ),
+            (
+                std::env::temp_dir().join("music-tag-info-numbers.ogg"),
+                Some(ogg),
+            ),
+        ];
+        for (path, bytes) in files {
+            if let Some(bytes) = bytes {
+                std::fs::write(&path, bytes).unwrap();
+            }
+            let mut tag = MusicTag::read_from_path(&path).unwrap();
+            tag.set_track_number(3);
+            tag.set_track_total(12);
+            tag.set_disc_number(1);
+            tag.write_to_path(&path).unwrap();
+            let mut tag = MusicTag::read_from_path(&path).unwrap();
+            let numbers = (
+                tag.track_number,
+                tag.track_total,
+                tag.disc_number,
+                tag.disc_total,
+            );
+            assert_eq!(numbers, (Some(3), Some(12), Some(1), None), "{:?}", path);
+
+            // No number may be invented for a total on its own.
+            tag.track_number = None;
+            tag.disc_number = None;
+            tag.disc_total = Some(2);
+            tag.write_to_path(&path).unwrap();
+            let tag = MusicTag::read_from_path(&path).unwrap();
+            assert_eq!(
+                (tag.track_number, tag.disc_number),
+                (None, None),
+                "{:?}",
+                path
+            );
+            let totals = (tag.track_total, tag.disc_total);
+            if tag.fmt == MusicFormat::Mp3 {
+                // TRCK and TPOS cannot hold a total alone.
+                assert_eq!(totals, (None, None));
+            } else {
+                assert_eq!(totals, (Some(12), Some(2)), "{:?}", path);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_unread_vorbis_aliases() {
+        let fields = [
+            ("COMMENT", "comment"),
+            ("DESCRIPTION", "description"),
+            ("ORGANIZATION", "organization"),
+            ("PUBLISHER", "publisher"),
+        ];
+        let mut flac = metaflac::Tag::new();
+        for (key, value) in fields {
+            flac.set_vorbis(key, vec![value]);
+        }
+        let mut flac_bytes = Vec::new();
+        flac.write_to(&mut flac_bytes).unwrap();
+        let ogg = super::super::ogg::tests::sample(&super::super::ogg::tests::comments(&fields));
+        let flac_path = std::env::temp_dir().join("music-tag-info-aliases.flac");
+        let ogg_path = std::env::temp_dir().join("music-tag-info-aliases.ogg");
+        std::fs::write(&flac_path, flac_bytes).unwrap();
+        std::fs::write(&ogg_path, ogg).unwrap();
+        let keys = |path: &Path| -> Vec<(String, String)> {
+            if path == flac_path {
+                let tag = metaflac::Tag::read_from_path(path).unwrap();
+                let mut keys: Vec<_> = tag
+                    .vorbis_comments()
+                    .unwrap()
+                    .comments
+                    .iter()
+                    .map(|(key, values)| (key.clone(), values.join(";")))
+                    .collect();
+                keys.sort();
+                keys
+            } else {
+                let file = super::super::ogg::OggFile::read_from(&std::fs::read(path).unwrap());
+                let mut keys = file.unwrap().comments.comments;
+                keys.sort();
+                keys
+            }
+        };
+        let pairs = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        for path in [&flac_path, &ogg_path] {
+            let mut tag = MusicTag::read_from_path(path).unwrap();
+            assert_eq!(tag.comment(), Some("comment"));
+            assert_eq!(tag.publisher(), Some("publisher"));
+            tag.write_to_path(path).unwrap();
+            // PUBLISHER was read and moves to LABEL; the others were not.
+            let expected = pairs(&[
+                ("COMMENT", "comment"),
+                ("DESCRIPTION", "description"),
+                ("LABEL", "publisher"),
+                ("ORGANIZATION", "organization"),
+            ]);
+            assert_eq!(keys(path), expected, "{:?}", path);
+
+            tag.comment = None;
+            tag.publisher = None;
+            tag.write_to_path(path).unwrap();
+            assert_eq!(keys(path), [], "{:?}", path);
+            let tag = MusicTag::read_from_path(path).unwrap();
+            assert_eq!((tag.comment(), tag.publisher()), (None, None));
+        }
+    }
+
+    #[test]
+    fn rejects_garbage_bytes() {
+        let garbage: Vec<u8> = (0..2000u32).map(|i| (i * 7919 % 251) as u8).collect();
+        let formats = [
+            MusicFormat::Mp3,
+            MusicFormat::Flac,
+            MusicFormat::M4a,
+            MusicFormat::Ogg,
+        ];
+        for fmt in formats {
+            assert!(MusicTag::read_from_bytes(garbage.clone(), fmt).is_err());
+            assert!(MusicTag::read_from_bytes(Vec::new(), fmt).is_err());
+        }
+        // Valid magic followed by junk.
+        for magic in [&b"fLaC"[..], b"OggS\0\x02", b"\0\0\0\x14ftypM4A "] {
+            let mut bytes = magic.to_vec();
+            bytes.extend_from_slice(&garbage);
+            assert!(MusicTag::read_from_bytes(bytes, MusicFormat::Mp3).is_err());
+        }
+    }
+
+    #[test]
+    fn reads_mp3_with_only_sylt() {
+        let path = temp_mp3("sylt");
+        let mut id3 = id3::Tag::read_from_path(&path).unwrap();
+        id3.add_frame(id3::frame::SynchronisedLyrics {
+            lang: "eng".into(),
+            timestamp_format: id3::frame::TimestampFormat::Ms,
+            content_type: id3::frame::SynchronisedLyricsType::Lyrics,
+            description: "synced".into(),
+            content: vec![(1500, "one\n".into()), (3000, "two".into())],
+        });
+        id3.write_to_path(&path, id3::Version::Id3v24).unwrap();
+
+        let tag = MusicTag::read_from_path(&path).unwrap();
+        let entries = tag.lyrics_entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].description, "synced");
+        assert_eq!(entries[0].text, "[00:01.500]one\n[00:03.000]two\n");
+        let lines: Vec<_> = tag.lyrics().unwrap().timed_lines().to_vec();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].time.as_millis(), 3000);
+        assert!(tag.lyrics_in("en").is_some());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn writes_colliding_id3_lyrics() {
+        let path = temp_mp3("lyrics");
+        let mut tag = MusicTag::read_from_path(&path).unwrap();
+        let entry = |text: &str, lang: Option<&str>| LyricsEntry {
+            text: text.into(),
+            lang: lang.map(Into::into),
+            description: String::new(),
+        };
+        tag.set_lyrics_entries(vec![
+            entry("[00:01.00]first", Some("en")),
+            entry("[00:02.00]second", Some("en")),
+            entry("third", Some("EN-gb")),
+            entry("none", None),
+        ]);
+        tag.write_to_path(&path).unwrap();
+
+        let id3 = id3::Tag::read_from_path(&path).unwrap();
+        let uslt: Vec<_> = id3
+            .lyrics()
+            .map(|l| (l.lang.as_str(), l.description.as_str(), l.text.as_str()))
+            .collect();
+        assert_eq!(
+            uslt,
+            [
+                ("eng", "", "[00:01.00]first"),
+                ("eng", "(2)", "[00:02.00]second"),
+                ("eng", "(3)", "third"),
+                ("und", "", "none"),
+            ]
+        );
+        let sylt: Vec<_> = id3.synchronised_lyrics().map(|s| &s.content).collect();
+        assert_eq!(sylt, [&vec![(1000, "first".to_string())]]);
+
+        let read = MusicTag::read_from_path(&path).unwrap();
+        let texts: Vec<_> = read.lyrics_entries().iter().map(|e| &e.text).collect();
+        assert_eq!(
+            texts,
+            ["[00:01.00]first", "[00:02.00]second", "third", "none"]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn orders_flac_lyrics() {
+        let mut flac = metaflac::Tag::new();
+        flac.set_vorbis("LYRICS_EN", vec!["b"]);
+        flac.set_vorbis("UNSYNCEDLYRICS", vec!["2"]);
+        flac.set_vorbis("LYRICS-DE", vec!["c"]);
+        flac.set_vorbis("LYRICS", vec!["1"]);
+        flac.set_vorbis("LYRICS-EN", vec!["a", "a2"]);
+        let tag = MusicTag::try_from((flac, None)).unwrap();
+        let entries: Vec<_> = tag
+            .lyrics_entries()
+            .iter()
+            .map(|e| (e.lang.as_deref(), e.text.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (None, "1"),
+                (None, "2"),
+                (Some("de"), "c"),
+                (Some("en"), "a"),
+                (Some("en"), "a2"),
+                (Some("en"), "b"),
+            ]
+        );
+        let text = |lyrics: Option<Lyrics>| lyrics.map(|l| l.lines().join("\n"));
+        assert_eq!(text(tag.lyrics_in("eng")), Some("a".into()));
+        assert_eq!(text(tag.lyrics_in("ger")), Some("c".into()));
+        assert_eq!(text(tag.lyrics_in("fra")), None);
+    }
+
+    #[test]
+    fn rejects_duplicate_mp3_picture_types() {
+        let path = temp_mp3("pictures");
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x03\0\0\0\x02\x08\x02\0\0\0".to_vec();
+        let mut tag = MusicTag::read_from_path(&path).unwrap();
+        tag.set_artworks(vec![(png.clone(), ImgFmt::PNG), (png.clone(), ImgFmt::PNG)])
+            .unwrap();
+        assert!(matches!(
+            tag.write_to_path(&path),
+            Err(crate::Error::FmtError(_))
+        ));
+        let mut back = tag.artworks().next().unwrap().clone();
+        back.picture_type = PictureType::CoverBack;
+        tag.set_artworks(vec![(png, ImgFmt::PNG)]).unwrap();
+        tag.add_artwork(back);
+        tag.write_to_path(&path).unwrap();
+        let types: Vec<_> = MusicTag::read_from_path(&path)
+            .unwrap()
+            .artworks()
+            .map(|artwork| artwork.picture_type)
+            .collect();
+        assert_eq!(types, [PictureType::CoverFront, PictureType::CoverBack]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn converts_mp4_artwork() {
+        let gif =
+            b"GIF89a\x01\0\x01\0\x80\0\0\xff\xff\xff\0\0\0,\0\0\0\0\x01\0\x01\0\0\x02\x02D\x01\0;";
+        let artwork = Artwork {
+            data: gif.to_vec(),
+            fmt: ImgFmt::detect(gif),
+            width: 1,
+            height: 1,
+            picture_type: PictureType::CoverFront,
+            description: String::new(),
+        };
+        assert_eq!(artwork.fmt, ImgFmt::GIF);
+        #[cfg(feature = "image")]
+        {
+            let png = mp4_artwork(&artwork).unwrap();
+            assert_eq!(png.fmt, mp4ameta::ImgFmt::Png);
+            assert_eq!(ImgFmt::detect(&png.data), ImgFmt::PNG);
+            assert_eq!(imagesize::blob_size(&png.data).unwrap().width, 1);
+        }
+        #[cfg(not(feature = "image"))]
+        assert!(matches!(
+            mp4_artwork(&artwork),
+            Err(crate::Error::FmtError(_))
+        ));
+    }
+}
@@ -0,0 +1,287 @@
+/// ISO-639-2 "undetermined", for ID3 frames whose language is unknown.
+pub(crate) const ID3_UNKNOWN_LANG: &str = "und";
+
+/// ISO-639-1 codes and their ISO-639-2 terminology codes.
+const ISO_639_1: [(&str, &str); 183] = [
+    ("aa", "aar"),
+    ("ab", "abk"),
+    ("ae", "ave"),
+    ("af", "afr"),
+    ("ak", "aka"),
+    ("am", "amh"),
+    ("an", "arg"),
+    ("ar", "ara"),
+    ("as", "asm"),
+    ("av", "ava"),
+    ("ay", "aym"),
+    ("az", "aze"),
+    ("ba", "bak"),
+    ("be", "bel"),
+    ("bg", "bul"),
+    ("bi", "bis"),
+    ("bm", "bam"),
+    ("bn", "ben"),
+    ("bo", "bod"),
+    ("br", "bre"),
+    ("bs", "bos"),
+    ("ca", "cat"),
+    ("ce", "che"),
+    ("ch", "cha"),
+    ("co", "cos"),
+    ("cr", "cre"),
+    ("cs", "ces"),
+    ("cu", "chu"),
+    ("cv", "chv"),
+    ("cy", "cym"),
+    ("da", "dan"),
+    ("de", "deu"),
+    ("dv", "div"),
+    ("dz", "dzo"),
+    ("ee", "ewe"),
+    ("el", "ell"),
+    ("en", "eng"),
+    ("eo", "epo"),
+    ("es", "spa"),
+    ("et", "est"),
+    ("eu", "eus"),
+    ("fa", "fas"),
+    ("ff", "ful"),
+    ("fi", "fin"),
+    ("fj", "fij"),
+    ("fo", "fao"),
+    ("fr", "fra"),
+    ("fy", "fry"),
+    ("ga", "gle"),
+    ("gd", "gla"),
+    ("gl", "glg"),
+    ("gn", "grn"),
+    ("gu", "guj"),
+    ("gv", "glv"),
+    ("ha", "hau"),
+    ("he", "heb"),
+    ("hi", "hin"),
+    ("ho", "hmo"),
+    ("hr", "hrv"),
+    ("ht", "hat"),
+    ("hu", "hun"),
+    ("hy", "hye"),
+    ("hz", "her"),
+    ("ia", "ina"),
+    ("id", "ind"),
+    ("ie", "ile"),
+    ("ig", "ibo"),
+    ("ii", "iii"),
+    ("ik", "ipk"),
+    ("io", "ido"),
+    ("is", "isl"),
+    ("it", "ita"),
+    ("iu", "iku"),
+    ("ja", "jpn"),
+    ("jv", "jav"),
+    ("ka", "kat"),
+    ("kg", "kon"),
+    ("ki", "kik"),
+    ("kj", "kua"),
+    ("kk", "kaz"),
+    ("kl", "kal"),
+    ("km", "khm"),
+    ("kn", "kan"),
+    ("ko", "kor"),
+    ("kr", "kau"),
+    ("ks", "kas"),
+    ("ku", "kur"),
+    ("kv", "kom"),
+    ("kw", "cor"),
+    ("ky", "kir"),
+    ("la", "lat"),
+    ("lb", "ltz"),
+    ("lg", "lug"),
+    ("li", "lim"),
+    ("ln", "lin"),
+    ("lo", "lao"),
+    ("lt", "lit"),
+    ("lu", "lub"),
+    ("lv", "lav"),
+    ("mg", "mlg"),
+    ("mh", "mah"),
+    ("mi", "mri"),
+    ("mk", "mkd"),
+    ("ml", "mal"),
+    ("mn", "mon"),
+    ("mr", "mar"),
+    ("ms", "msa"),
+    ("mt", "mlt"),
+    ("my", "mya"),
+    ("na", "nau"),
+    ("nb", "nob"),
+    ("nd", "nde"),
+    ("ne", "nep"),
+    ("ng", "ndo"),
+    ("nl", "nld"),
+    ("nn", "nno"),
+    ("no", "nor"),
+    ("nr", "nbl"),
+    ("nv", "nav"),
+    ("ny", "nya"),
+    ("oc", "oci"),
+    ("oj", "oji"),
+    ("om", "orm"),
+    ("or", "ori"),
+    ("os", "oss"),
+    ("pa", "pan"),
+    ("pi", "pli"),
+    ("pl", "pol"),
+    ("ps", "pus"),
+    ("pt", "por"),
+    ("qu", "que"),
+    ("rm", "roh"),
+    ("rn", "run"),
+    ("ro", "ron"),
+    ("ru", "rus"),
+    ("rw", "kin"),
+    ("sa", "san"),
+    ("sc", "srd"),
+    ("sd", "snd"),
+    ("se", "sme"),
+    ("sg", "sag"),
+    ("si", "sin"),
+    ("sk", "slk"),
+    ("sl", "slv"),
+    ("sm", "smo"),
+    ("sn", "sna"),
+    ("so", "som"),
+    ("sq", "sqi"),
+    ("sr", "srp"),
+    ("ss", "ssw"),
+    ("st", "sot"),
+    ("su", "sun"),
+    ("sv", "swe"),
+    ("sw", "swa"),
+    ("ta", "tam"),
+    ("te", "tel"),
+    ("tg", "tgk"),
+    ("th", "tha"),
+    ("ti", "tir"),
+    ("tk", "tuk"),
+    ("tl", "tgl"),
+    ("tn", "tsn"),
+    ("to", "ton"),
+    ("tr", "tur"),
+    ("ts", "tso"),
+    ("tt", "tat"),
+    ("tw", "twi"),
+    ("ty", "tah"),
+    ("ug", "uig"),
+    ("uk", "ukr"),
+    ("ur", "urd"),
+    ("uz", "uzb"),
+    ("ve", "ven"),
+    ("vi", "vie"),
+    ("vo", "vol"),
+    ("wa", "wln"),
+    ("wo", "wol"),
+    ("xh", "xho"),
+    ("yi", "yid"),
+    ("yo", "yor"),
+    ("za", "zha"),
+    ("zh", "zho"),
+    ("zu", "zul"),
+];
+
+/// ISO-639-2 bibliographic codes and the terminology codes above.
+const ISO_639_2_B: [(&str, &str); 20] = [
+    ("alb", "sqi"),
+    ("arm", "hye"),
+    ("baq", "eus"),
+    ("bur", "mya"),
+    ("chi", "zho"),
+    ("cze", "ces"),
+    ("dut", "nld"),
+    ("fre", "fra"),
+    ("geo", "kat"),
+    ("ger", "deu"),
+    ("gre", "ell"),
+    ("ice", "isl"),
+    ("mac", "mkd"),
+    ("mao", "mri"),
+    ("may", "msa"),
+    ("per", "fas"),
+    ("rum", "ron"),
+    ("slo", "slk"),
+    ("tib", "bod"),
+    ("wel", "cym"),
+];
+
+/// The three-letter code ID3 frames store for `lang`. ISO-639-1 codes are
+/// mapped, region suffixes as in `en-US` dropped, and anything else that is
+/// not a three-letter code becomes `und`.
+pub(crate) fn id3_lang_code(lang: &str) -> String {
+    let primary = lang
+        .trim()
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match primary.len() {
+        2 => ISO_639_1
+            .binary_search_by_key(&primary.as_str(), |(code, _)| code)
+            .map_or(ID3_UNKNOWN_LANG, |i| ISO_639_1[i].1)
+            .into(),
+        3 if primary.bytes().all(|b| b.is_ascii_lowercase()) => primary,
+        _ => ID3_UNKNOWN_LANG.into(),
+    }
+}
+
+/// Whether `a` and `b` name the same language, each given as an ISO-639-1
+/// code or either form of ISO-639-2 code. Unknown codes match only
+/// themselves.
+pub(crate) fn same_language(a: &str, b: &str) -> bool {
+    fn terminology(lang: &str) -> String {
+        let code = id3_lang_code(lang);
+        ISO_639_2_B
+            .binary_search_by_key(&code.as_str(), |(code, _)| code)
+            .map_or(code, |i| ISO_639_2_B[i].1.into())
+    }
+    match terminology(a) {
+        code if code == ID3_UNKNOWN_LANG => a.trim().eq_ignore_ascii_case(b.trim()),
+        code => code == terminology(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_is_sorted() {
+        assert!(ISO_639_1.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(ISO_639_2_B.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn maps_codes() {
+        assert_eq!(id3_lang_code("en"), "eng");
+        assert_eq!(id3_lang_code("ZH"), "zho");
+        assert_eq!(id3_lang_code("pt-BR"), "por");
+        assert_eq!(id3_lang_code("ja_JP"), "jpn");
+        assert_eq!(id3_lang_code("eng"), "eng");
+        assert_eq!(id3_lang_code("Ger"), "ger");
+        assert_eq!(id3_lang_code("qq"), "und");
+        assert_eq!(id3_lang_code(""), "und");
+        assert_eq!(id3_lang_code("english"), "und");
+        assert_eq!(id3_lang_code("e1g"), "und");
+    }
+
+    #[test]
+    fn compares_languages() {
+        assert!(same_language("eng", "en"));
+        assert!(same_language("EN-us", "eng"));
+        assert!(same_language("de", "ger"));
+        assert!(same_language("ger", "deu"));
+        assert!(same_language("qaa", "QAA"));
+        assert!(same_language("Klingon", "klingon"));
+        assert!(!same_language("en", "de"));
+        assert!(!same_language("", "und"));
+        assert!(!same_language("qq", "und"));
+    }
+}
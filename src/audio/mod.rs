@@ -1,6 +1,17 @@
+mod genre;
 mod info;
+mod lang;
+#[cfg(feature = "image")]
+mod normalize;
+mod ogg;
 mod pic;
 mod player;
-pub use pic::{Artwork, ImgFmt};
+mod properties;
+mod sidecar;
+pub use pic::{Artwork, ImgFmt, PictureType};
 
-pub use info::{MusicFormat, MusicTag};
+pub use info::{LyricsEntry, MusicFormat, MusicTag};
+#[cfg(feature = "image")]
+pub use normalize::ArtworkPolicy;
+pub use player::{Player, PlayerEvent, PlayerEvents, PlayerOutput};
+pub use properties::AudioProperties;
@@ -0,0 +1,336 @@
+//! Resizing and re-encoding of artwork, behind the `image` feature.
+
+use std::io::Cursor;
+
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, ImageFormat};
+
+use super::{Artwork, ImgFmt};
+use crate::Result;
+
+/// JPEG quality used when only the size of an image changes.
+const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+/// Limits artworks are brought within before they are written, see
+/// [`MusicTag::set_artwork_policy`](super::MusicTag::set_artwork_policy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArtworkPolicy {
+    /// Larger images are scaled down to fit, keeping their aspect ratio.
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+    /// Larger images are re-encoded as JPEG, lowering the quality in steps
+    /// of 10 until they fit or the quality reaches 10.
+    pub max_bytes: Option<usize>,
+    pub png_to_jpeg: bool,
+    pub jpeg_quality: u8,
+    pub strip_exif: bool,
+}
+
+impl Default for ArtworkPolicy {
+    /// No limits; EXIF data is stripped.
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            max_height: None,
+            max_bytes: None,
+            png_to_jpeg: false,
+            jpeg_quality: DEFAULT_JPEG_QUALITY,
+            strip_exif: true,
+        }
+    }
+}
+
+impl ArtworkPolicy {
+    /// Returns `artwork` brought within the limits. Images already within
+    /// them are not re-encoded.
+    pub fn apply(&self, artwork: &Artwork) -> Result<Artwork> {
+        let mut artwork = artwork.clone();
+        if self.strip_exif {
+            artwork.strip_exif();
+        }
+        let max_width = self.max_width.unwrap_or(u32::MAX);
+        let max_height = self.max_height.unwrap_or(u32::MAX);
+        let too_large = artwork.width > max_width as usize || artwork.height > max_height as usize;
+        let too_heavy =
+            |artwork: &Artwork| self.max_bytes.is_some_and(|max| artwork.data.len() > max);
+        let png_to_jpeg = self.png_to_jpeg && artwork.fmt == ImgFmt::PNG;
+        if !too_large && !png_to_jpeg && !too_heavy(&artwork) {
+            return Ok(artwork);
+        }
+        let mut image = artwork.decode()?;
+        if too_large {
+            image = image.resize(max_width, max_height, FilterType::Lanczos3);
+            // Scaling down may be enough to fit `max_bytes`.
+            if !png_to_jpeg {
+                artwork.encode(&image, artwork.fmt, self.jpeg_quality)?;
+                if !too_heavy(&artwork) {
+                    return Ok(artwork);
+                }
+            }
+        }
+        let mut quality = self.jpeg_quality.clamp(1, 100);
+        loop {
+            artwork.encode(&image, ImgFmt::JPEG, quality)?;
+            match self.max_bytes {
+                Some(max) if artwork.data.len() > max && quality > 10 => {
+                    quality = quality.saturating_sub(10).max(10)
+                }
+                _ => return Ok(artwork),
+            }
+        }
+    }
+}
+
+impl Artwork {
+    /// Scales the image down to fit within `max_width` x `max_height`,
+    /// keeping its aspect ratio. Returns whether it was resized.
+    pub fn downscale(&mut self, max_width: u32, max_height: u32) -> Result<bool> {
+        if self.width <= max_width as usize && self.height <= max_height as usize {
+            return Ok(false);
+        }
+        let image = self
+            .decode()?
+            .resize(max_width, max_height, FilterType::Lanczos3);
+        self.encode(&image, self.fmt, DEFAULT_JPEG_QUALITY)?;
+        Ok(true)
+    }
+    /// Re-encodes the image as JPEG with `quality` from 1 to 100.
+    /// Transparency is dropped.
+    pub fn to_jpeg(&mut self, quality: u8) -> Result<()> {
+        let image = self.decode()?;
+        self.encode(&image, ImgFmt::JPEG, quality)
+    }
+    /// Re-encodes the image as PNG.
+    pub fn to_png(&mut self) -> Result<()> {
+        let image = self.decode()?;
+        self.encode(&image, ImgFmt::PNG, DEFAULT_JPEG_QUALITY)
+    }
+    /// Removes EXIF data from JPEG and PNG images without re-encoding them.
+    /// Returns whether there was any.
+    pub fn strip_exif(&mut self) -> bool {
+        let stripped = match self.fmt {
+            ImgFmt::JPEG => strip_jpeg_exif(&self.data),
+            ImgFmt::PNG => strip_png_exif(&self.data),
+            _ => None,
+        };
+        match stripped {
+            Some(data) if data.len() != self.data.len() => {
+                self.data = data;
+                true
+            }
+            _ => false,
+        }
+    }
+    fn decode(&self) -> Result<DynamicImage> {
+        Ok(image::load_from_memory(&self.data)?)
+    }
+    /// Formats the image crate cannot write become JPEG.
+    fn encode(&mut self, image: &DynamicImage, fmt: ImgFmt, quality: u8) -> Result<()> {
+        let mut data = Vec::new();
+        let fmt = match fmt {
+            ImgFmt::PNG => {
+                image.write_to(&mut Cursor::new(&mut data), ImageFormat::Png)?;
+                ImgFmt::PNG
+            }
+            ImgFmt::BMP => {
+                image.write_to(&mut Cursor::new(&mut data), ImageFormat::Bmp)?;
+                ImgFmt::BMP
+            }
+            _ => {
+                let encoder = JpegEncoder::new_with_quality(&mut data, quality.clamp(1, 100));
+                image.to_rgb8().write_with_encoder(encoder)?;
+                ImgFmt::JPEG
+            }
+        };
+        self.width = image.width() as usize;
+        self.height = image.height() as usize;
+        self.data = data;
+        self.fmt = fmt;
+        Ok(())
+    }
+}
+
+/// Drops APP1 segments holding EXIF. `None` if the data is not a JPEG file.
+fn strip_jpeg_exif(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut out = data[..2].to_vec();
+    let mut pos = 2;
+    loop {
+        if *data.get(pos)? != 0xFF {
+            return None;
+        }
+        let marker = *data.get(pos + 1)?;
+        match marker {
+            // Fill byte
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            // Start of scan: entropy-coded data follows up to the end.
+            0xDA | 0xD9 => {
+                out.extend_from_slice(&data[pos..]);
+                return Some(out);
+            }
+            // Markers without a length
+            0x01 | 0xD0..=0xD7 => {
+                out.extend_from_slice(&data[pos..pos + 2]);
+                pos += 2;
+                continue;
+            }
+            _ => (),
+        }
+        let len = u16::from_be_bytes([*data.get(pos + 2)?, *data.get(pos + 3)?]) as usize;
+        if len < 2 {
+            return None;
+        }
+        let segment = data.get(pos..pos + 2 + len)?;
+        if !(marker == 0xE1 && segment[4..].starts_with(b"Exif\0\0")) {
+            out.extend_from_slice(segment);
+        }
+        pos += 2 + len;
+    }
+}
+
+/// Drops `eXIf` chunks. `None` if the data is not a PNG file.
+fn strip_png_exif(data: &[u8]) -> Option<Vec<u8>> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if !data.starts_with(SIGNATURE) {
+        return None;
+    }
+    let mut out = SIGNATURE.to_vec();
+    let mut pos = SIGNATURE.len();
+    while pos < data.len() {
+        let len = u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        // length, type, data and CRC
+        let chunk = data.get(pos..pos + 12 + len)?;
+        if &chunk[4..8] != b"eXIf" {
+            out.extend_from_slice(chunk);
+        }
+        pos += chunk.len();
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::PictureType;
+    use image::{ImageBuffer, Rgb};
+
+    /// A PNG with enough detail not to compress to almost nothing.
+    fn png(width: u32, height: u32) -> Artwork {
+        let image = ImageBuffer::from_fn(width, height, |x, y| {
+            Rgb([
+                (x * 7 % 256) as u8,
+                (y * 13 % 256) as u8,
+                ((x ^ y) % 256) as u8,
+            ])
+        });
+        let mut data = Vec::new();
+        DynamicImage::ImageRgb8(image)
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .unwrap();
+        Artwork {
+            data,
+            fmt: ImgFmt::PNG,
+            width: width as usize,
+            height: height as usize,
+            picture_type: PictureType::CoverFront,
+            description: String::new(),
+        }
+    }
+
+    #[test]
+    fn keeps_images_within_limits() {
+        let artwork = png(30, 20);
+        let policy = ArtworkPolicy {
+            max_width: Some(30),
+            max_bytes: Some(artwork.data.len()),
+            ..Default::default()
+        };
+        assert_eq!(policy.apply(&artwork).unwrap().data, artwork.data);
+    }
+
+    #[test]
+    fn resizing_can_satisfy_max_bytes() {
+        let artwork = png(900, 700);
+        let policy = ArtworkPolicy {
+            max_width: Some(300),
+            max_height: Some(300),
+            max_bytes: Some(artwork.data.len() / 4),
+            ..Default::default()
+        };
+        let resized = policy.apply(&artwork).unwrap();
+        assert_eq!(
+            (resized.fmt, resized.width, resized.height),
+            (ImgFmt::PNG, 300, 233)
+        );
+        assert!(resized.data.len() <= artwork.data.len() / 4);
+    }
+
+    #[test]
+    fn heavy_images_become_jpeg() {
+        let artwork = png(900, 700);
+        let policy = ArtworkPolicy {
+            max_width: Some(600),
+            max_bytes: Some(artwork.data.len() / 20),
+            ..Default::default()
+        };
+        let jpeg = policy.apply(&artwork).unwrap();
+        assert_eq!(
+            (jpeg.fmt, jpeg.width, jpeg.height),
+            (ImgFmt::JPEG, 600, 467)
+        );
+        assert_eq!(ImgFmt::detect(&jpeg.data), ImgFmt::JPEG);
+        let policy = ArtworkPolicy {
+            png_to_jpeg: true,
+            ..Default::default()
+        };
+        assert_eq!(policy.apply(&png(30, 20)).unwrap().fmt, ImgFmt::JPEG);
+    }
+
+    /// `data` with `inserted` spliced in at `at`.
+    fn splice(data: &[u8], at: usize, inserted: &[u8]) -> Vec<u8> {
+        [&data[..at], inserted, &data[at..]].concat()
+    }
+
+    #[test]
+    fn strips_jpeg_exif() {
+        let mut jpeg = Vec::new();
+        let image = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(4, 3, Rgb([9, 99, 199])));
+        image
+            .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+        let exif = b"\xFF\xE1\0\x10Exif\0\0MM\0\x2a\0\0\0\x08";
+        // An APP1 segment that is not EXIF, such as XMP, stays.
+        let other = b"\xFF\xE1\0\x06http";
+        let tagged = splice(&jpeg, 2, &[&exif[..], other].concat());
+
+        let stripped = strip_jpeg_exif(&tagged).unwrap();
+        assert_eq!(stripped, splice(&jpeg, 2, other));
+        let decoded = image::load_from_memory(&stripped).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (4, 3));
+        assert_eq!(strip_jpeg_exif(&png(4, 3).data), None);
+        for len in 0..tagged.len() {
+            strip_jpeg_exif(&tagged[..len]);
+        }
+    }
+
+    #[test]
+    fn strips_png_exif() {
+        let data = png(4, 3).data;
+        // After the signature and the IHDR chunk.
+        let exif = b"\0\0\0\x04eXIfMM\0\x2a\0\0\0\0";
+        let tagged = splice(&data, 33, exif);
+
+        let stripped = strip_png_exif(&tagged).unwrap();
+        assert_eq!(stripped, data);
+        let decoded = image::load_from_memory(&stripped).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (4, 3));
+        assert_eq!(strip_png_exif(b"\xFF\xD8\xFF\xD9"), None);
+        for len in 0..tagged.len() {
+            strip_png_exif(&tagged[..len]);
+        }
+    }
+}
@@ -0,0 +1,455 @@
+//! Just enough of the Ogg container to replace the Vorbis comment header.
+//!
+//! The header packets of the first logical stream are re-paginated, every
+//! following page of that stream gets its sequence number shifted and its
+//! CRC recomputed, and pages of other streams are copied through untouched.
+
+use crate::{Error, Result};
+
+const CAPTURE: &[u8; 4] = b"OggS";
+const HEADER_LEN: usize = 27;
+const CONTINUED: u8 = 0x01;
+const BOS: u8 = 0x02;
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut r = (i as u32) << 24;
+        let mut j = 0;
+        while j < 8 {
+            r = if r & 0x8000_0000 != 0 {
+                (r << 1) ^ 0x04c1_1db7
+            } else {
+                r << 1
+            };
+            j += 1;
+        }
+        table[i] = r;
+        i += 1;
+    }
+    table
+};
+
+fn crc32(data: &[u8]) -> u32 {
+    data.iter().fold(0, |crc, &b| {
+        (crc << 8) ^ CRC_TABLE[((crc >> 24) as u8 ^ b) as usize]
+    })
+}
+
+fn fmt_error(msg: &str) -> Error {
+    Error::FmtError(msg.into())
+}
+
+struct Page {
+    header_type: u8,
+    granule: u64,
+    serial: u32,
+    sequence: u32,
+    lacing: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl Page {
+    fn read(buf: &[u8]) -> Result<(Page, usize)> {
+        if buf.len() < HEADER_LEN || &buf[0..4] != CAPTURE {
+            return Err(fmt_error("Invalid Ogg page"));
+        }
+        let segments = buf[26] as usize;
+        let lacing = buf
+            .get(HEADER_LEN..HEADER_LEN + segments)
+            .ok_or_else(|| fmt_error("Truncated Ogg page"))?
+            .to_vec();
+        let start = HEADER_LEN + segments;
+        let len = lacing.iter().map(|&l| l as usize).sum::<usize>();
+        let data = buf
+            .get(start..start + len)
+            .ok_or_else(|| fmt_error("Truncated Ogg page"))?
+            .to_vec();
+        let u32_at = |i: usize| u32::from_le_bytes(buf[i..i + 4].try_into().unwrap());
+        let page = Page {
+            header_type: buf[5],
+            granule: u64::from_le_bytes(buf[6..14].try_into().unwrap()),
+            serial: u32_at(14),
+            sequence: u32_at(18),
+            lacing,
+            data,
+        };
+        Ok((page, start + len))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.lacing.len() + self.data.len());
+        bytes.extend_from_slice(CAPTURE);
+        bytes.push(0);
+        bytes.push(self.header_type);
+        bytes.extend_from_slice(&self.granule.to_le_bytes());
+        bytes.extend_from_slice(&self.serial.to_le_bytes());
+        bytes.extend_from_slice(&self.sequence.to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.push(self.lacing.len() as u8);
+        bytes.extend_from_slice(&self.lacing);
+        bytes.extend_from_slice(&self.data);
+        let crc = crc32(&bytes);
+        bytes[22..26].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+}
+
+/// Lays `packets` out on pages of the stream `serial`, numbering them from
+/// `sequence`. The last packet always finishes its page.
+fn paginate(packets: &[Vec<u8>], serial: u32, mut sequence: u32, bos: bool) -> Vec<Page> {
+    // (segment length, offset into packet, packet index, ends the packet)
+    let mut segments = Vec::new();
+    for (index, packet) in packets.iter().enumerate() {
+        let mut offset = 0;
+        loop {
+            let len = (packet.len() - offset).min(255);
+            let last = len < 255;
+            segments.push((len, offset, index, last));
+            offset += len;
+            if last {
+                break;
+            }
+        }
+    }
+    let mut pages = Vec::new();
+    let mut continued = false;
+    for chunk in segments.chunks(255) {
+        let mut header_type = if continued { CONTINUED } else { 0 };
+        if bos && pages.is_empty() {
+            header_type |= BOS;
+        }
+        let mut data = Vec::new();
+        for &(len, offset, index, _) in chunk {
+            data.extend_from_slice(&packets[index][offset..offset + len]);
+        }
+        continued = chunk.last().is_some_and(|s| !s.3);
+        // Header packets have a granule position of zero; pages on which no
+        // packet finishes carry -1.
+        let granule = if chunk.iter().any(|s| s.3) {
+            0
+        } else {
+            u64::MAX
+        };
+        pages.push(Page {
+            header_type,
+            granule,
+            serial,
+            sequence,
+            lacing: chunk.iter().map(|s| s.0 as u8).collect(),
+            data,
+        });
+        sequence += 1;
+    }
+    pages
+}
+
+const VORBIS_COMMENT: &[u8; 7] = b"\x03vorbis";
+
+/// The comment header of a Vorbis stream, with the comments kept in order.
+pub(crate) struct VorbisComments {
+    pub vendor: String,
+    pub comments: Vec<(String, String)>,
+}
+
+impl VorbisComments {
+    fn from_packet(packet: &[u8]) -> Result<Self> {
+        fn take<'a>(body: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+            if body.len() < len {
+                return Err(fmt_error("Invalid Vorbis comment header"));
+            }
+            let (head, tail) = body.split_at(len);
+            *body = tail;
+            Ok(head)
+        }
+        fn take_u32(body: &mut &[u8]) -> Result<usize> {
+            let bytes = take(body, 4)?;
+            Ok(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+        }
+        let mut body = packet
+            .strip_prefix(VORBIS_COMMENT)
+            .ok_or_else(|| fmt_error("Invalid Vorbis comment header"))?;
+        let vendor_len = take_u32(&mut body)?;
+        let vendor = String::from_utf8_lossy(take(&mut body, vendor_len)?).into_owned();
+        let count = take_u32(&mut body)?;
+        let mut comments = Vec::new();
+        for _ in 0..count {
+            let len = take_u32(&mut body)?;
+            let comment = String::from_utf8_lossy(take(&mut body, len)?).into_owned();
+            if let Some((key, value)) = comment.split_once('=') {
+                comments.push((key.to_owned(), value.to_owned()));
+            }
+        }
+        Ok(Self { vendor, comments })
+    }
+
+    fn to_packet(&self) -> Vec<u8> {
+        let mut packet = VORBIS_COMMENT.to_vec();
+        packet.extend_from_slice(&(self.vendor.len() as u32).to_le_bytes());
+        packet.extend_from_slice(self.vendor.as_bytes());
+        packet.extend_from_slice(&(self.comments.len() as u32).to_le_bytes());
+        for (key, value) in &self.comments {
+            let len = key.len() + 1 + value.len();
+            packet.extend_from_slice(&(len as u32).to_le_bytes());
+            packet.extend_from_slice(key.as_bytes());
+            packet.push(b'=');
+            packet.extend_from_slice(value.as_bytes());
+        }
+        // framing bit
+        packet.push(1);
+        packet
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.comments
+            .iter()
+            .any(|(k, _)| k.eq_ignore_ascii_case(key))
+    }
+
+    /// Removes every comment whose key is `key`.
+    pub fn remove(&mut self, key: &str) {
+        self.comments.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+    }
+
+    pub fn push(&mut self, key: &str, value: impl Into<String>) {
+        self.comments.push((key.to_ascii_uppercase(), value.into()));
+    }
+}
+
+/// An Ogg Vorbis file split into its header packets and the remaining pages.
+pub(crate) struct OggFile {
+    serial: u32,
+    identification: Vec<u8>,
+    pub comments: VorbisComments,
+    setup: Vec<u8>,
+    /// Number of pages the original header packets occupied.
+    header_pages: u32,
+    /// Pages of other streams found between the header pages.
+    interleaved: Vec<Page>,
+    rest: Vec<Page>,
+}
+
+impl OggFile {
+    pub fn read_from(buf: &[u8]) -> Result<Self> {
+        let mut pages = Vec::new();
+        let mut pos = 0;
+        while pos < buf.len() {
+            let (page, len) = Page::read(&buf[pos..])?;
+            pages.push(page);
+            pos += len;
+        }
+        let serial = pages
+            .first()
+            .ok_or_else(|| fmt_error("Empty Ogg file"))?
+            .serial;
+        let mut packets = Vec::new();
+        let mut packet = Vec::new();
+        let mut header_pages = 0;
+        let mut index = 0;
+        while packets.len() < 3 {
+            let page = pages
+                .get(index)
+                .ok_or_else(|| fmt_error("Missing Vorbis header packets"))?;
+            index += 1;
+            if page.serial != serial {
+                continue;
+            }
+            header_pages += 1;
+            let mut offset = 0;
+            for &len in &page.lacing {
+                let len = len as usize;
+                packet.extend_from_slice(&page.data[offset..offset + len]);
+                offset += len;
+                if len < 255 {
+                    packets.push(std::mem::take(&mut packet));
+                }
+            }
+        }
+        if packets.len() != 3 || !packet.is_empty() {
+            return Err(fmt_error("Audio data shares a page with Vorbis headers"));
+        }
+        if !packets[0].starts_with(b"\x01vorbis") {
+            return Err(Error::NotSupportedError);
+        }
+        let setup = packets.pop().unwrap();
+        let comments = VorbisComments::from_packet(&packets.pop().unwrap())?;
+        let identification = packets.pop().unwrap();
+        let rest = pages.split_off(index);
+        let interleaved = pages.into_iter().filter(|p| p.serial != serial).collect();
+        Ok(Self {
+            serial,
+            identification,
+            comments,
+            setup,
+            header_pages,
+            interleaved,
+            rest,
+        })
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        let identification = paginate(&[self.identification], self.serial, 0, true);
+        let comments = paginate(
+            &[self.comments.to_packet(), self.setup],
+            self.serial,
+            identification.len() as u32,
+            false,
+        );
+        let shift = (identification.len() + comments.len()) as i64 - self.header_pages as i64;
+        let mut bytes = Vec::new();
+        // Other streams' headers stay right behind our beginning-of-stream page.
+        for page in identification
+            .into_iter()
+            .chain(self.interleaved)
+            .chain(comments)
+        {
+            bytes.append(&mut page.to_bytes());
+        }
+        for mut page in self.rest {
+            if page.serial == self.serial {
+                page.sequence = (page.sequence as i64 + shift) as u32;
+            }
+            bytes.append(&mut page.to_bytes());
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    const SERIAL: u32 = 0x1234;
+
+    /// A Vorbis stream with the given comments and two pages of audio. The
+    /// identification header is valid, stereo at 44.1 kHz.
+    pub(crate) fn sample(comments: &VorbisComments) -> Vec<u8> {
+        let mut identification = b"\x01vorbis".to_vec();
+        identification.extend_from_slice(&0u32.to_le_bytes());
+        identification.push(2);
+        identification.extend_from_slice(&44100u32.to_le_bytes());
+        identification.extend_from_slice(&[0; 12]);
+        // Block sizes 256 and 2048, then the framing bit.
+        identification.extend_from_slice(&[0xb8, 1]);
+        let mut pages = paginate(&[identification], SERIAL, 0, true);
+        let sequence = pages.len() as u32;
+        pages.extend(paginate(
+            &[comments.to_packet(), b"\x05vorbis setup".to_vec()],
+            SERIAL,
+            sequence,
+            false,
+        ));
+        for (i, data) in [[1u8; 300], [2u8; 300]].iter().enumerate() {
+            let mut page = paginate(&[data.to_vec()], SERIAL, pages.len() as u32, false).remove(0);
+            page.granule = 1024 * (i as u64 + 1);
+            pages.push(page);
+        }
+        pages.iter().flat_map(Page::to_bytes).collect()
+    }
+
+    pub(crate) fn comments(pairs: &[(&str, &str)]) -> VorbisComments {
+        let mut comments = VorbisComments {
+            vendor: "test".into(),
+            comments: Vec::new(),
+        };
+        for (key, value) in pairs {
+            comments.push(key, *value);
+        }
+        comments
+    }
+
+    fn pages(mut buf: &[u8]) -> Vec<Page> {
+        let mut pages = Vec::new();
+        while !buf.is_empty() {
+            let (page, len) = Page::read(buf).unwrap();
+            // The checksum is computed with its own field zeroed.
+            let mut raw = buf[..len].to_vec();
+            let crc = u32::from_le_bytes(raw[22..26].try_into().unwrap());
+            raw[22..26].fill(0);
+            assert_eq!(crc32(&raw), crc);
+            pages.push(page);
+            buf = &buf[len..];
+        }
+        pages
+    }
+
+    #[test]
+    fn rewrites_comments() {
+        let original = sample(&comments(&[("TITLE", "Old"), ("ARTIST", "Someone")]));
+        let mut file = OggFile::read_from(&original).unwrap();
+        file.comments.remove("title");
+        file.comments.push("title", "New");
+        let bytes = file.into_bytes();
+
+        let file = OggFile::read_from(&bytes).unwrap();
+        assert_eq!(file.comments.vendor, "test");
+        assert_eq!(
+            file.comments.comments,
+            [
+                ("ARTIST".to_owned(), "Someone".to_owned()),
+                ("TITLE".to_owned(), "New".to_owned())
+            ]
+        );
+        let pages = pages(&bytes);
+        assert_eq!(pages[0].header_type, BOS);
+        let audio = &pages[pages.len() - 2..];
+        assert_eq!(audio[0].data, [1; 300]);
+        assert_eq!(audio[1].granule, 2048);
+    }
+
+    #[test]
+    fn comment_spanning_pages() {
+        let original = sample(&comments(&[("TITLE", "Short")]));
+        let before = pages(&original).len();
+        let mut file = OggFile::read_from(&original).unwrap();
+        let long = "x".repeat(200_000);
+        file.comments.push("COMMENT", long.clone());
+        let bytes = file.into_bytes();
+
+        let pages = pages(&bytes);
+        // 200 kB needs at least four pages of 255 segments of 255 bytes.
+        assert!(pages.len() >= before + 3);
+        for (i, page) in pages.iter().enumerate() {
+            assert_eq!(page.sequence, i as u32);
+        }
+        // Every header page but the last one continues the packet.
+        let headers = &pages[1..pages.len() - 2];
+        assert!(headers[1..].iter().all(|p| p.header_type & CONTINUED != 0));
+        assert!(headers[..headers.len() - 1]
+            .iter()
+            .all(|p| p.granule == u64::MAX));
+        assert_eq!(headers.last().unwrap().granule, 0);
+
+        let file = OggFile::read_from(&bytes).unwrap();
+        assert_eq!(file.comments.comments[1], ("COMMENT".to_owned(), long));
+    }
+
+    #[test]
+    fn keeps_key_case() {
+        let mut original = comments(&[("ARTIST", "Someone")]);
+        original
+            .comments
+            .push(("replaygain_track_gain".into(), "-3 dB".into()));
+        original.comments.push(("Title".into(), "Old".into()));
+        let mut file = OggFile::read_from(&sample(&original)).unwrap();
+        file.comments.remove("TITLE");
+        file.comments.push("title", "New");
+
+        let file = OggFile::read_from(&file.into_bytes()).unwrap();
+        assert_eq!(
+            file.comments.comments,
+            [
+                ("ARTIST".to_owned(), "Someone".to_owned()),
+                ("replaygain_track_gain".to_owned(), "-3 dB".to_owned()),
+                ("TITLE".to_owned(), "New".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_page() {
+        let bytes = sample(&comments(&[]));
+        assert!(OggFile::read_from(&bytes[..bytes.len() - 10]).is_err());
+    }
+}
@@ -1,29 +1,247 @@
 use std::fmt::Debug;
 
-#[derive(Debug)]
+use imagesize::ImageType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ImgFmt {
     JPEG,
     PNG,
+    GIF,
+    BMP,
+    WebP,
+    TIFF,
+    Unknown,
 }
 impl ImgFmt {
     pub fn from_mime(mime: &str) -> Self {
-        match mime {
+        match mime.to_ascii_lowercase().as_str() {
+            "image/jpeg" | "image/jpg" => ImgFmt::JPEG,
             "image/png" => ImgFmt::PNG,
-            _ => ImgFmt::JPEG,
+            "image/gif" => ImgFmt::GIF,
+            "image/bmp" | "image/x-ms-bmp" => ImgFmt::BMP,
+            "image/webp" => ImgFmt::WebP,
+            "image/tiff" => ImgFmt::TIFF,
+            _ => ImgFmt::Unknown,
+        }
+    }
+    /// Detects the format from the image data.
+    pub fn detect(data: &[u8]) -> Self {
+        match imagesize::image_type(data) {
+            Ok(ImageType::Jpeg) => ImgFmt::JPEG,
+            Ok(ImageType::Png) => ImgFmt::PNG,
+            Ok(ImageType::Gif) => ImgFmt::GIF,
+            Ok(ImageType::Bmp) => ImgFmt::BMP,
+            Ok(ImageType::Webp) => ImgFmt::WebP,
+            Ok(ImageType::Tiff) => ImgFmt::TIFF,
+            _ => ImgFmt::Unknown,
+        }
+    }
+    /// The usual file extension, `bin` for unknown formats.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImgFmt::JPEG => "jpg",
+            ImgFmt::PNG => "png",
+            ImgFmt::GIF => "gif",
+            ImgFmt::BMP => "bmp",
+            ImgFmt::WebP => "webp",
+            ImgFmt::TIFF => "tiff",
+            ImgFmt::Unknown => "bin",
+        }
+    }
+    /// MP4 covers can only be JPEG, PNG or BMP.
+    pub(crate) fn to_mp4(self) -> Option<mp4ameta::ImgFmt> {
+        match self {
+            ImgFmt::JPEG => Some(mp4ameta::ImgFmt::Jpeg),
+            ImgFmt::PNG => Some(mp4ameta::ImgFmt::Png),
+            ImgFmt::BMP => Some(mp4ameta::ImgFmt::Bmp),
+            _ => None,
+        }
+    }
+}
+
+/// What a picture shows, as numbered by ID3 and FLAC.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PictureType {
+    Other,
+    /// A 32x32 PNG file icon.
+    Icon,
+    OtherIcon,
+    #[default]
+    CoverFront,
+    CoverBack,
+    /// A page of the booklet.
+    Leaflet,
+    /// The disc itself.
+    Media,
+    LeadArtist,
+    Artist,
+    Conductor,
+    Band,
+    Composer,
+    Lyricist,
+    RecordingLocation,
+    DuringRecording,
+    DuringPerformance,
+    ScreenCapture,
+    BrightFish,
+    Illustration,
+    BandLogo,
+    PublisherLogo,
+}
+
+impl PictureType {
+    const ALL: [PictureType; 21] = [
+        PictureType::Other,
+        PictureType::Icon,
+        PictureType::OtherIcon,
+        PictureType::CoverFront,
+        PictureType::CoverBack,
+        PictureType::Leaflet,
+        PictureType::Media,
+        PictureType::LeadArtist,
+        PictureType::Artist,
+        PictureType::Conductor,
+        PictureType::Band,
+        PictureType::Composer,
+        PictureType::Lyricist,
+        PictureType::RecordingLocation,
+        PictureType::DuringRecording,
+        PictureType::DuringPerformance,
+        PictureType::ScreenCapture,
+        PictureType::BrightFish,
+        PictureType::Illustration,
+        PictureType::BandLogo,
+        PictureType::PublisherLogo,
+    ];
+    /// The number stored in ID3 and FLAC picture frames.
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+    /// Unknown numbers give [`PictureType::Other`].
+    pub fn from_code(code: u32) -> PictureType {
+        Self::ALL
+            .get(code as usize)
+            .copied()
+            .unwrap_or(PictureType::Other)
+    }
+}
+
+impl From<id3::frame::PictureType> for PictureType {
+    fn from(value: id3::frame::PictureType) -> Self {
+        Self::from_code(u8::from(value) as u32)
+    }
+}
+impl From<PictureType> for id3::frame::PictureType {
+    fn from(value: PictureType) -> Self {
+        use id3::frame::PictureType as P;
+        match value {
+            PictureType::Other => P::Other,
+            PictureType::Icon => P::Icon,
+            PictureType::OtherIcon => P::OtherIcon,
+            PictureType::CoverFront => P::CoverFront,
+            PictureType::CoverBack => P::CoverBack,
+            PictureType::Leaflet => P::Leaflet,
+            PictureType::Media => P::Media,
+            PictureType::LeadArtist => P::LeadArtist,
+            PictureType::Artist => P::Artist,
+            PictureType::Conductor => P::Conductor,
+            PictureType::Band => P::Band,
+            PictureType::Composer => P::Composer,
+            PictureType::Lyricist => P::Lyricist,
+            PictureType::RecordingLocation => P::RecordingLocation,
+            PictureType::DuringRecording => P::DuringRecording,
+            PictureType::DuringPerformance => P::DuringPerformance,
+            PictureType::ScreenCapture => P::ScreenCapture,
+            PictureType::BrightFish => P::BrightFish,
+            PictureType::Illustration => P::Illustration,
+            PictureType::BandLogo => P::BandLogo,
+            PictureType::PublisherLogo => P::PublisherLogo,
+        }
+    }
+}
+impl From<metaflac::block::PictureType> for PictureType {
+    fn from(value: metaflac::block::PictureType) -> Self {
+        Self::from_code(value as u32)
+    }
+}
+impl From<PictureType> for metaflac::block::PictureType {
+    fn from(value: PictureType) -> Self {
+        use metaflac::block::PictureType as P;
+        match value {
+            PictureType::Other => P::Other,
+            PictureType::Icon => P::Icon,
+            PictureType::OtherIcon => P::OtherIcon,
+            PictureType::CoverFront => P::CoverFront,
+            PictureType::CoverBack => P::CoverBack,
+            PictureType::Leaflet => P::Leaflet,
+            PictureType::Media => P::Media,
+            PictureType::LeadArtist => P::LeadArtist,
+            PictureType::Artist => P::Artist,
+            PictureType::Conductor => P::Conductor,
+            PictureType::Band => P::Band,
+            PictureType::Composer => P::Composer,
+            PictureType::Lyricist => P::Lyricist,
+            PictureType::RecordingLocation => P::RecordingLocation,
+            PictureType::DuringRecording => P::DuringRecording,
+            PictureType::DuringPerformance => P::DuringPerformance,
+            PictureType::ScreenCapture => P::ScreenCapture,
+            PictureType::BrightFish => P::BrightFish,
+            PictureType::Illustration => P::Illustration,
+            PictureType::BandLogo => P::BandLogo,
+            PictureType::PublisherLogo => P::PublisherLogo,
         }
     }
 }
+/// Symphonia has no key for [`PictureType::Other`] and
+/// [`PictureType::BrightFish`].
+impl From<Option<symphonia::core::meta::StandardVisualKey>> for PictureType {
+    fn from(value: Option<symphonia::core::meta::StandardVisualKey>) -> Self {
+        use symphonia::core::meta::StandardVisualKey as K;
+        match value {
+            None => PictureType::Other,
+            Some(K::FileIcon) => PictureType::Icon,
+            Some(K::OtherIcon) => PictureType::OtherIcon,
+            Some(K::FrontCover) => PictureType::CoverFront,
+            Some(K::BackCover) => PictureType::CoverBack,
+            Some(K::Leaflet) => PictureType::Leaflet,
+            Some(K::Media) => PictureType::Media,
+            Some(K::LeadArtistPerformerSoloist) => PictureType::LeadArtist,
+            Some(K::ArtistPerformer) => PictureType::Artist,
+            Some(K::Conductor) => PictureType::Conductor,
+            Some(K::BandOrchestra) => PictureType::Band,
+            Some(K::Composer) => PictureType::Composer,
+            Some(K::Lyricist) => PictureType::Lyricist,
+            Some(K::RecordingLocation) => PictureType::RecordingLocation,
+            Some(K::RecordingSession) => PictureType::DuringRecording,
+            Some(K::Performance) => PictureType::DuringPerformance,
+            Some(K::ScreenCapture) => PictureType::ScreenCapture,
+            Some(K::Illustration) => PictureType::Illustration,
+            Some(K::BandArtistLogo) => PictureType::BandLogo,
+            Some(K::PublisherStudioLogo) => PictureType::PublisherLogo,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Artwork {
     pub height: usize,
     pub width: usize,
     pub data: Vec<u8>,
     pub fmt: ImgFmt,
+    /// MP3 files keep one picture per type, M4A files only front covers.
+    pub picture_type: PictureType,
+    pub description: String,
 }
 impl Artwork {
     pub fn mime_type(&self) -> &'static str {
         match self.fmt {
             ImgFmt::JPEG => "image/jpeg",
             ImgFmt::PNG => "image/png",
+            ImgFmt::GIF => "image/gif",
+            ImgFmt::BMP => "image/bmp",
+            ImgFmt::WebP => "image/webp",
+            ImgFmt::TIFF => "image/tiff",
+            ImgFmt::Unknown => "application/octet-stream",
         }
     }
 }
@@ -33,6 +251,8 @@ impl Debug for Artwork {
             .field("height", &self.height)
             .field("width", &self.width)
             .field("fmt", &self.fmt)
+            .field("picture_type", &self.picture_type)
+            .field("description", &self.description)
             .finish()
     }
 }
@@ -0,0 +1,784 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, RecvError, RecvTimeoutError, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use rodio::{
+    dynamic_mixer::{self, DynamicMixer, DynamicMixerController},
+    OutputStream, OutputStreamHandle, Source,
+};
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error as SymphoniaError,
+    formats::{FormatReader, SeekMode, SeekTo},
+    units::Time,
+};
+
+use super::{info::probe_format, MusicTag};
+use crate::{AudioReader, Error, Result};
+
+/// Format of the samples a headless output consumes.
+const HEADLESS_CHANNELS: u16 = 2;
+const HEADLESS_SAMPLE_RATE: u32 = 44100;
+/// How much audio a headless output consumes at once.
+const HEADLESS_CHUNK: Duration = Duration::from_millis(10);
+/// Ticks queued before new ones are dropped.
+const TICK_CAPACITY: usize = 64;
+
+/// Where the player sends its samples.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlayerOutput {
+    /// The default audio device.
+    Device,
+    /// Nowhere. Samples are still consumed in real time, so positions and
+    /// events behave as with a device.
+    Null,
+    /// A 16-bit 44.1 kHz stereo WAV file, written in real time.
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerEvent {
+    /// The position, sent every tick interval while playing.
+    Tick(Duration),
+    /// The track played to its end.
+    Finished,
+}
+
+/// Sends events to [`PlayerEvents`], dropping ticks while too many are
+/// unread.
+#[derive(Clone)]
+struct EventSender {
+    sender: Sender<PlayerEvent>,
+    queued_ticks: Arc<AtomicUsize>,
+}
+
+impl EventSender {
+    fn tick(&self, position: Duration) {
+        let queued = self.queued_ticks.fetch_add(1, Ordering::SeqCst);
+        if queued >= TICK_CAPACITY || self.sender.send(PlayerEvent::Tick(position)).is_err() {
+            self.queued_ticks.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    fn finished(&self) {
+        let _ = self.sender.send(PlayerEvent::Finished);
+    }
+}
+
+/// The events of a [`Player`]. While 64 ticks are unread, new ones are
+/// dropped; [`PlayerEvent::Finished`] is always delivered.
+pub struct PlayerEvents {
+    receiver: Receiver<PlayerEvent>,
+    queued_ticks: Arc<AtomicUsize>,
+}
+
+impl PlayerEvents {
+    fn received(&self, event: PlayerEvent) -> PlayerEvent {
+        if let PlayerEvent::Tick(_) = event {
+            self.queued_ticks.fetch_sub(1, Ordering::SeqCst);
+        }
+        event
+    }
+
+    pub fn recv(&self) -> std::result::Result<PlayerEvent, RecvError> {
+        self.receiver.recv().map(|event| self.received(event))
+    }
+
+    pub fn try_recv(&self) -> std::result::Result<PlayerEvent, TryRecvError> {
+        self.receiver.try_recv().map(|event| self.received(event))
+    }
+
+    pub fn recv_timeout(
+        &self,
+        timeout: Duration,
+    ) -> std::result::Result<PlayerEvent, RecvTimeoutError> {
+        self.receiver
+            .recv_timeout(timeout)
+            .map(|event| self.received(event))
+    }
+
+    /// The events received so far, without waiting.
+    pub fn try_iter(&self) -> impl Iterator<Item = PlayerEvent> + '_ {
+        std::iter::from_fn(|| self.try_recv().ok())
+    }
+}
+
+fn event_channel() -> (EventSender, PlayerEvents) {
+    let (sender, receiver) = mpsc::channel();
+    let queued_ticks = Arc::new(AtomicUsize::new(0));
+    let events = PlayerEvents {
+        receiver,
+        queued_ticks: queued_ticks.clone(),
+    };
+    (
+        EventSender {
+            sender,
+            queued_ticks,
+        },
+        events,
+    )
+}
+
+/// State shared between the player and the source being played.
+struct Controls {
+    /// Changed whenever the track is replaced or stopped, which ends the
+    /// source of the previous one.
+    track: AtomicU64,
+    paused: AtomicBool,
+    finished: AtomicBool,
+    /// `f32` bits.
+    volume: AtomicU32,
+    /// In milliseconds.
+    position: AtomicU64,
+    /// In milliseconds, 0 disables ticks.
+    tick: AtomicU64,
+    seek: Mutex<Option<Duration>>,
+}
+
+impl Controls {
+    fn is_playing(&self) -> bool {
+        !self.paused.load(Ordering::SeqCst) && !self.finished.load(Ordering::SeqCst)
+    }
+}
+
+/// A track decoded by symphonia, played as a rodio source.
+struct TrackSource {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    channels: u16,
+    sample_rate: u32,
+    total_duration: Option<Duration>,
+    samples: Vec<f32>,
+    sample_buf: Option<SampleBuffer<f32>>,
+    offset: usize,
+    /// Frames before this timestamp are dropped after a seek.
+    skip_to: Option<u64>,
+    /// Set by a seek past the end.
+    ended: bool,
+    /// Frames played, including the ones skipped by seeking.
+    frames: u64,
+    next_tick: u64,
+    track: u64,
+    controls: Arc<Controls>,
+    events: EventSender,
+}
+
+impl TrackSource {
+    fn new(
+        format: Box<dyn FormatReader>,
+        track: u64,
+        controls: Arc<Controls>,
+        events: EventSender,
+    ) -> Result<Self> {
+        let track_info = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| Error::FmtError("No audio track".into()))?;
+        let (track_id, params) = (track_info.id, track_info.codec_params.clone());
+        let decoder = symphonia::default::get_codecs().make(&params, &DecoderOptions::default())?;
+        let channels = params
+            .channels
+            .map(|c| c.count() as u16)
+            .ok_or_else(|| Error::FmtError("Unknown channel count".into()))?;
+        let sample_rate = params
+            .sample_rate
+            .ok_or_else(|| Error::FmtError("Unknown sample rate".into()))?;
+        let total_duration = params
+            .n_frames
+            .filter(|_| sample_rate > 0)
+            .and_then(|n| Duration::try_from_secs_f64(n as f64 / sample_rate as f64).ok());
+        Ok(Self {
+            track_id,
+            format,
+            decoder,
+            channels,
+            sample_rate,
+            total_duration,
+            samples: Vec::new(),
+            sample_buf: None,
+            offset: 0,
+            skip_to: None,
+            ended: false,
+            frames: 0,
+            next_tick: 0,
+            track,
+            controls,
+            events,
+        })
+    }
+
+    fn position_millis(&self) -> u64 {
+        self.frames * 1000 / self.sample_rate as u64
+    }
+
+    fn seek(&mut self, to: Duration) {
+        let time = Time::new(to.as_secs(), to.subsec_nanos() as f64 / 1e9);
+        let seek_to = SeekTo::Time {
+            time,
+            track_id: Some(self.track_id),
+        };
+        self.samples.clear();
+        self.offset = 0;
+        match self.format.seek(SeekMode::Accurate, seek_to) {
+            Ok(seeked) => {
+                self.decoder.reset();
+                self.skip_to = Some(seeked.required_ts);
+                self.frames = seeked.required_ts;
+                self.ended = false;
+            }
+            // Past the end
+            Err(_) => {
+                self.frames = (to.as_secs_f64() * self.sample_rate as f64) as u64;
+                self.ended = true;
+            }
+        }
+        let tick = self.controls.tick.load(Ordering::SeqCst);
+        let position = self.position_millis();
+        self.next_tick = if tick == 0 {
+            0
+        } else {
+            position - position % tick
+        };
+        self.controls.position.store(position, Ordering::SeqCst);
+    }
+
+    /// Decodes the next packet into `samples`, returns false at the end.
+    fn decode(&mut self) -> bool {
+        while !self.ended {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::ResetRequired) => {
+                    self.decoder.reset();
+                    continue;
+                }
+                Err(_) => return false,
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(_) => return false,
+            };
+            let frames = decoded.frames() as u64;
+            if self
+                .sample_buf
+                .as_ref()
+                .is_none_or(|buf| buf.capacity() < decoded.capacity())
+            {
+                self.sample_buf = Some(SampleBuffer::new(
+                    decoded.capacity() as u64,
+                    *decoded.spec(),
+                ));
+            }
+            let sample_buf = self.sample_buf.as_mut().unwrap();
+            sample_buf.copy_interleaved_ref(decoded);
+            let skip = match self.skip_to {
+                Some(ts) if packet.ts() + frames <= ts => continue,
+                Some(ts) => {
+                    self.skip_to = None;
+                    ts.saturating_sub(packet.ts())
+                }
+                None => 0,
+            };
+            self.samples.clear();
+            self.samples
+                .extend_from_slice(&sample_buf.samples()[skip as usize * self.channels as usize..]);
+            self.offset = 0;
+            if !self.samples.is_empty() {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl Iterator for TrackSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.controls.track.load(Ordering::SeqCst) != self.track {
+            return None;
+        }
+        if self.offset.is_multiple_of(self.channels as usize) {
+            let seek = self.controls.seek.lock().unwrap().take();
+            if let Some(to) = seek {
+                self.seek(to);
+            }
+        }
+        if self.controls.paused.load(Ordering::SeqCst) {
+            return Some(0.0);
+        }
+        if self.offset >= self.samples.len() && !self.decode() {
+            // The source may be polled again after it ended.
+            if !self.controls.finished.swap(true, Ordering::SeqCst) {
+                self.events.finished();
+            }
+            return None;
+        }
+        let sample = self.samples[self.offset];
+        self.offset += 1;
+        if self.offset.is_multiple_of(self.channels as usize) {
+            self.frames += 1;
+            let position = self.position_millis();
+            self.controls.position.store(position, Ordering::SeqCst);
+            let tick = self.controls.tick.load(Ordering::SeqCst);
+            if tick != 0 && position >= self.next_tick {
+                self.events.tick(Duration::from_millis(position));
+                self.next_tick = position - position % tick + tick;
+            }
+        }
+        Some(sample * f32::from_bits(self.controls.volume.load(Ordering::SeqCst)))
+    }
+}
+
+impl Source for TrackSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.total_duration
+    }
+}
+
+/// Pulls samples from `mixer` in real time while something is playing.
+fn run_headless(
+    mut mixer: DynamicMixer<f32>,
+    controls: Arc<Controls>,
+    shutdown: Arc<AtomicBool>,
+    mut writer: Option<hound::WavWriter<BufWriter<File>>>,
+) {
+    let chunk = (HEADLESS_SAMPLE_RATE as u128 * HEADLESS_CHUNK.as_millis() / 1000) as usize
+        * HEADLESS_CHANNELS as usize;
+    let mut next = Instant::now();
+    while !shutdown.load(Ordering::SeqCst) {
+        if !controls.is_playing() {
+            thread::sleep(HEADLESS_CHUNK);
+            next = Instant::now();
+            continue;
+        }
+        for _ in 0..chunk {
+            let sample = mixer.next().unwrap_or_default();
+            if let Some(writer) = &mut writer {
+                let _ = writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16);
+            }
+        }
+        next += HEADLESS_CHUNK;
+        if let Some(wait) = next.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+    }
+    if let Some(writer) = writer {
+        let _ = writer.finalize();
+    }
+}
+
+enum Output {
+    Device {
+        /// Playback stops when the stream is dropped.
+        _stream: OutputStream,
+        handle: OutputStreamHandle,
+    },
+    Headless {
+        mixer: Arc<DynamicMixerController<f32>>,
+        shutdown: Arc<AtomicBool>,
+        thread: Option<JoinHandle<()>>,
+    },
+}
+
+enum Input {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+/// Plays one track at a time.
+///
+/// A loaded track starts paused; call [`Player::play`] to start it. Events
+/// are read from [`Player::events`].
+pub struct Player {
+    output: Output,
+    input: Option<Input>,
+    controls: Arc<Controls>,
+    duration: Option<Duration>,
+    sender: EventSender,
+    events: PlayerEvents,
+}
+
+impl Player {
+    /// Creates a player on the default audio device.
+    pub fn new() -> Result<Self> {
+        Self::with_output(PlayerOutput::Device)
+    }
+
+    pub fn with_output(output: PlayerOutput) -> Result<Self> {
+        let controls = Arc::new(Controls {
+            track: AtomicU64::new(0),
+            paused: AtomicBool::new(true),
+            finished: AtomicBool::new(false),
+            volume: AtomicU32::new(1.0f32.to_bits()),
+            position: AtomicU64::new(0),
+            tick: AtomicU64::new(100),
+            seek: Mutex::new(None),
+        });
+        let output = match output {
+            PlayerOutput::Device => {
+                let (stream, handle) = OutputStream::try_default()?;
+                Output::Device {
+                    _stream: stream,
+                    handle,
+                }
+            }
+            PlayerOutput::Null | PlayerOutput::File(_) => {
+                let writer = match output {
+                    PlayerOutput::File(path) => {
+                        let spec = hound::WavSpec {
+                            channels: HEADLESS_CHANNELS,
+                            sample_rate: HEADLESS_SAMPLE_RATE,
+                            bits_per_sample: 16,
+                            sample_format: hound::SampleFormat::Int,
+                        };
+                        Some(hound::WavWriter::create(path, spec)?)
+                    }
+                    _ => None,
+                };
+                let (mixer, source) = dynamic_mixer::mixer(HEADLESS_CHANNELS, HEADLESS_SAMPLE_RATE);
+                let shutdown = Arc::new(AtomicBool::new(false));
+                let thread = {
+                    let controls = controls.clone();
+                    let shutdown = shutdown.clone();
+                    thread::spawn(move || run_headless(source, controls, shutdown, writer))
+                };
+                Output::Headless {
+                    mixer,
+                    shutdown,
+                    thread: Some(thread),
+                }
+            }
+        };
+        let (sender, events) = event_channel();
+        Ok(Self {
+            output,
+            input: None,
+            controls,
+            duration: None,
+            sender,
+            events,
+        })
+    }
+
+    /// Loads the file `tag` was read from.
+    pub fn load(&mut self, tag: &MusicTag) -> Result<()> {
+        let path = tag
+            .as_path()
+            .ok_or_else(|| Error::FmtError("MusicTag has no path".into()))?;
+        self.load_path(path)
+    }
+
+    pub fn load_path(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        self.open(Input::Path(path.as_ref().to_path_buf()), Duration::ZERO)
+    }
+
+    pub fn load_reader(&mut self, reader: AudioReader) -> Result<()> {
+        self.open(Input::Bytes(reader.buf), Duration::ZERO)
+    }
+
+    /// Loads `input` paused at `position`. On error the previous track stays
+    /// loaded.
+    fn open(&mut self, input: Input, position: Duration) -> Result<()> {
+        self.start(&input, position)?;
+        self.input = Some(input);
+        Ok(())
+    }
+
+    /// Replaces the playing source with a new one for `input`, paused at
+    /// `position`. Errors opening `input` leave the current source playing;
+    /// if the output refuses the new source, the current one is finished.
+    fn start(&mut self, input: &Input, position: Duration) -> Result<()> {
+        let format = match input {
+            Input::Path(path) => {
+                let ext = path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or_default();
+                probe_format(Box::new(File::open(path)?), ext)?
+            }
+            Input::Bytes(buf) => probe_format(Box::new(AudioReader::new(buf.clone())), "")?,
+        };
+        let track = self.controls.track.load(Ordering::SeqCst) + 1;
+        let source = TrackSource::new(format, track, self.controls.clone(), self.sender.clone())?;
+        let duration = source.total_duration;
+        self.controls.track.store(track, Ordering::SeqCst);
+        self.controls.paused.store(true, Ordering::SeqCst);
+        self.controls.finished.store(false, Ordering::SeqCst);
+        self.controls
+            .position
+            .store(position.as_millis() as u64, Ordering::SeqCst);
+        *self.controls.seek.lock().unwrap() = (!position.is_zero()).then_some(position);
+        let played = match &self.output {
+            Output::Device { handle, .. } => handle.play_raw(source),
+            Output::Headless { mixer, .. } => {
+                mixer.add(source);
+                Ok(())
+            }
+        };
+        if let Err(err) = played {
+            // The previous source has ended, so playing starts it over.
+            self.controls.finished.store(true, Ordering::SeqCst);
+            return Err(err.into());
+        }
+        self.duration = duration;
+        Ok(())
+    }
+
+    /// Starts or resumes playback. A finished track starts over.
+    pub fn play(&mut self) -> Result<()> {
+        if self.controls.finished.load(Ordering::SeqCst) {
+            if let Some(input) = self.input.take() {
+                let started = self.start(&input, Duration::ZERO);
+                self.input = Some(input);
+                started?;
+            }
+        }
+        if self.input.is_some() {
+            self.controls.paused.store(false, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    pub fn pause(&self) {
+        self.controls.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Unloads the track.
+    pub fn stop(&mut self) {
+        self.controls.track.fetch_add(1, Ordering::SeqCst);
+        self.controls.paused.store(true, Ordering::SeqCst);
+        self.controls.finished.store(false, Ordering::SeqCst);
+        self.controls.position.store(0, Ordering::SeqCst);
+        self.input = None;
+        self.duration = None;
+    }
+
+    pub fn seek(&mut self, position: Duration) -> Result<()> {
+        if self.controls.finished.load(Ordering::SeqCst) {
+            let paused = self.controls.paused.load(Ordering::SeqCst);
+            if let Some(input) = self.input.take() {
+                let started = self.start(&input, position);
+                self.input = Some(input);
+                started?;
+            }
+            self.controls.paused.store(paused, Ordering::SeqCst);
+        } else if self.input.is_some() {
+            *self.controls.seek.lock().unwrap() = Some(position);
+            self.controls
+                .position
+                .store(position.as_millis() as u64, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.controls.volume.load(Ordering::SeqCst))
+    }
+
+    /// `1.0` is the original volume.
+    pub fn set_volume(&self, volume: f32) {
+        self.controls
+            .volume
+            .store(volume.max(0.0).to_bits(), Ordering::SeqCst);
+    }
+
+    pub fn position(&self) -> Duration {
+        Duration::from_millis(self.controls.position.load(Ordering::SeqCst))
+    }
+
+    /// The length of the loaded track, when the container tells.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.controls.paused.load(Ordering::SeqCst)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.controls.finished.load(Ordering::SeqCst)
+    }
+
+    /// How often [`PlayerEvent::Tick`] is sent, 100ms by default.
+    /// `Duration::ZERO` disables it.
+    pub fn set_tick_interval(&self, interval: Duration) {
+        self.controls
+            .tick
+            .store(interval.as_millis() as u64, Ordering::SeqCst);
+    }
+
+    pub fn events(&self) -> &PlayerEvents {
+        &self.events
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        self.stop();
+        if let Output::Headless {
+            shutdown, thread, ..
+        } = &mut self.output
+        {
+            shutdown.store(true, Ordering::SeqCst);
+            if let Some(thread) = thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `millis` of a 44.1 kHz mono sine and returns its path.
+    fn wav(name: &str, millis: u32) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("music-tag-player-{}.wav", name));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..44100 * millis / 1000 {
+            let t = i as f32 / 44100.0;
+            let sample = (t * 440.0 * std::f32::consts::TAU).sin() * 0.5;
+            writer
+                .write_sample((sample * i16::MAX as f32) as i16)
+                .unwrap();
+        }
+        writer.finalize().unwrap();
+        path
+    }
+
+    fn wait_finished(player: &Player) -> Vec<PlayerEvent> {
+        let mut events = Vec::new();
+        loop {
+            let event = player
+                .events()
+                .recv_timeout(Duration::from_secs(5))
+                .unwrap();
+            events.push(event);
+            if event == PlayerEvent::Finished {
+                return events;
+            }
+        }
+    }
+
+    #[test]
+    fn plays_headless() {
+        let path = wav("null", 300);
+        let mut player = Player::with_output(PlayerOutput::Null).unwrap();
+        player.set_tick_interval(Duration::from_millis(50));
+        player.load_path(&path).unwrap();
+        assert_eq!(player.duration(), Some(Duration::from_millis(300)));
+        assert!(player.is_paused());
+        player.play().unwrap();
+        let events = wait_finished(&player);
+        let ticks: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                PlayerEvent::Tick(position) => Some(position.as_millis()),
+                _ => None,
+            })
+            .collect();
+        assert!(ticks.len() >= 5, "{:?}", ticks);
+        assert!(ticks.windows(2).all(|w| w[0] < w[1]), "{:?}", ticks);
+        assert!(player.is_finished());
+        assert_eq!(player.position(), Duration::from_millis(300));
+
+        // A finished track starts over, here from a seek position.
+        player.seek(Duration::from_millis(200)).unwrap();
+        assert!(!player.is_finished());
+        player.play().unwrap();
+        wait_finished(&player);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn writes_file_output() {
+        let input = wav("input", 200);
+        let output = std::env::temp_dir().join("music-tag-player-output.wav");
+        let mut player = Player::with_output(PlayerOutput::File(output.clone())).unwrap();
+        player.load_path(&input).unwrap();
+        player.play().unwrap();
+        wait_finished(&player);
+        drop(player);
+        let reader = hound::WavReader::open(&output).unwrap();
+        assert_eq!(reader.spec().channels, HEADLESS_CHANNELS);
+        assert_eq!(reader.spec().sample_rate, HEADLESS_SAMPLE_RATE);
+        let samples = reader.len() / HEADLESS_CHANNELS as u32;
+        assert!(samples >= 44100 / 5, "{}", samples);
+        std::fs::remove_file(input).unwrap();
+        std::fs::remove_file(output).unwrap();
+    }
+
+    #[test]
+    fn failed_load_keeps_track() {
+        let path = wav("keep", 100);
+        let mut player = Player::with_output(PlayerOutput::Null).unwrap();
+        player.load_path(&path).unwrap();
+        let track = player.controls.track.load(Ordering::SeqCst);
+        assert!(player.load_path(path.with_extension("missing")).is_err());
+        assert!(player
+            .load_reader(AudioReader::new(b"not audio".to_vec()))
+            .is_err());
+        assert_eq!(player.controls.track.load(Ordering::SeqCst), track);
+        assert_eq!(player.duration(), Some(Duration::from_millis(100)));
+        player.play().unwrap();
+        wait_finished(&player);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn keeps_finished_with_unread_ticks() {
+        let path = wav("unread", 200);
+        let mut player = Player::with_output(PlayerOutput::Null).unwrap();
+        player.set_tick_interval(Duration::from_millis(1));
+        player.load_path(&path).unwrap();
+        for _ in 0..2 {
+            player.play().unwrap();
+            while !player.is_finished() {
+                thread::sleep(Duration::from_millis(10));
+            }
+            // About 200 ticks were sent, but only the first ones are kept.
+            let events: Vec<_> = player.events().try_iter().collect();
+            let (finished, ticks) = events.split_last().unwrap();
+            assert_eq!(*finished, PlayerEvent::Finished);
+            assert_eq!(ticks.len(), TICK_CAPACITY);
+            assert!(ticks.iter().all(|e| matches!(e, PlayerEvent::Tick(_))));
+            // Reading the ticks makes room for new ones.
+            player.seek(Duration::ZERO).unwrap();
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
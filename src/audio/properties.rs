@@ -0,0 +1,866 @@
+use std::{
+    io::{Read, Seek, SeekFrom},
+    time::Duration,
+};
+
+use super::MusicFormat;
+use crate::{Error, Result};
+
+/// Properties of the audio stream, as opposed to the tag.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioProperties {
+    pub duration: Duration,
+    /// Average bitrate in kbit/s.
+    pub bitrate: u32,
+    pub sample_rate: u32,
+    pub channels: u8,
+    /// Only known for PCM based codecs such as FLAC and ALAC.
+    pub bits_per_sample: Option<u8>,
+    pub codec: String,
+    /// Whether the stream is encoded with a variable bitrate.
+    pub vbr: bool,
+}
+
+impl AudioProperties {
+    /// Reads the stream properties from the start of `reader`.
+    pub fn read_from(reader: &mut (impl Read + Seek), fmt: MusicFormat) -> Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+        match fmt {
+            MusicFormat::Flac => read_flac(reader),
+            MusicFormat::M4a => read_mp4(reader),
+            MusicFormat::Mp3 => read_mpeg(reader),
+            MusicFormat::Ogg => read_ogg(reader),
+        }
+    }
+}
+
+fn fmt_error(msg: &str) -> Error {
+    Error::FmtError(msg.into())
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut buf = [0; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_u32_be(reader: &mut impl Read) -> Result<u32> {
+    Ok(u32::from_be_bytes(read_array(reader)?))
+}
+
+/// `units / rate` seconds, or zero if the rate is zero or the result does
+/// not fit in a [`Duration`].
+fn units_to_duration(units: f64, rate: f64) -> Duration {
+    if rate > 0.0 {
+        Duration::try_from_secs_f64(units / rate).unwrap_or_default()
+    } else {
+        Duration::ZERO
+    }
+}
+
+fn bitrate(bytes: u64, duration: Duration) -> u32 {
+    let secs = duration.as_secs_f64();
+    if secs > 0.0 {
+        (bytes as f64 * 8.0 / secs / 1000.0).round() as u32
+    } else {
+        0
+    }
+}
+
+/// Skips an ID3v2 tag at the current position, if there is one.
+pub(super) fn skip_id3v2(reader: &mut (impl Read + Seek)) -> Result<()> {
+    let start = reader.stream_position()?;
+    let header: [u8; 10] = match read_array(reader) {
+        Ok(header) => header,
+        Err(_) => {
+            reader.seek(SeekFrom::Start(start))?;
+            return Ok(());
+        }
+    };
+    if &header[0..3] == b"ID3" {
+        let size = header[6..10]
+            .iter()
+            .fold(0u64, |size, &b| (size << 7) | (b & 0x7F) as u64);
+        let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+        reader.seek(SeekFrom::Start(start + 10 + size + footer))?;
+    } else {
+        reader.seek(SeekFrom::Start(start))?;
+    }
+    Ok(())
+}
+
+/// Parses the 34 bytes of a FLAC STREAMINFO block.
+fn parse_streaminfo(info: &[u8; 34]) -> AudioProperties {
+    let sample_rate = (info[10] as u32) << 12 | (info[11] as u32) << 4 | (info[12] as u32) >> 4;
+    let channels = ((info[12] >> 1) & 0x07) + 1;
+    let bits_per_sample = (((info[12] & 0x01) << 4) | (info[13] >> 4)) + 1;
+    let total_samples = ((info[13] & 0x0F) as u64) << 32
+        | u32::from_be_bytes(info[14..18].try_into().unwrap()) as u64;
+    let duration = units_to_duration(total_samples as f64, sample_rate as f64);
+    AudioProperties {
+        duration,
+        bitrate: 0,
+        sample_rate,
+        channels,
+        bits_per_sample: Some(bits_per_sample),
+        codec: "FLAC".into(),
+        vbr: true,
+    }
+}
+
+fn read_flac(reader: &mut (impl Read + Seek)) -> Result<AudioProperties> {
+    skip_id3v2(reader)?;
+    if &read_array::<4>(reader)? != b"fLaC" {
+        return Err(fmt_error("Missing fLaC marker"));
+    }
+    let mut properties = None;
+    loop {
+        let header: [u8; 4] = read_array(reader)?;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]);
+        if header[0] & 0x7F == 0 && len >= 34 {
+            properties = Some(parse_streaminfo(&read_array(reader)?));
+            reader.seek(SeekFrom::Current(len as i64 - 34))?;
+        } else {
+            reader.seek(SeekFrom::Current(len as i64))?;
+        }
+        if header[0] & 0x80 != 0 {
+            break;
+        }
+    }
+    let mut properties = properties.ok_or_else(|| fmt_error("Missing STREAMINFO"))?;
+    let audio_start = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    properties.bitrate = bitrate(end.saturating_sub(audio_start), properties.duration);
+    Ok(properties)
+}
+
+/// Reads the next MP4 atom header, returning its type and the size of its
+/// content.
+fn read_atom(reader: &mut impl Read, remain: u64) -> Result<([u8; 4], u64)> {
+    let size = read_u32_be(reader)? as u64;
+    let kind: [u8; 4] = read_array(reader)?;
+    let (size, header) = match size {
+        0 => (remain, 8),
+        1 => (u64::from_be_bytes(read_array(reader)?), 16),
+        size => (size, 8),
+    };
+    if size < header || size > remain {
+        return Err(fmt_error("Invalid atom size"));
+    }
+    Ok((kind, size - header))
+}
+
+/// Calls `f` for every atom in the next `len` bytes. `f` may read part of
+/// the content; the rest is skipped.
+fn for_each_atom<R: Read + Seek>(
+    reader: &mut R,
+    len: u64,
+    mut f: impl FnMut(&mut R, [u8; 4], u64) -> Result<()>,
+) -> Result<()> {
+    let end = reader.stream_position()? + len;
+    loop {
+        let pos = reader.stream_position()?;
+        if pos + 8 > end {
+            return Ok(());
+        }
+        let (kind, size) = read_atom(reader, end - pos)?;
+        let content = reader.stream_position()?;
+        f(reader, kind, size)?;
+        reader.seek(SeekFrom::Start(content + size))?;
+    }
+}
+
+#[derive(Default)]
+struct Mp4Track {
+    is_audio: bool,
+    codec: Option<[u8; 4]>,
+    object_type: u8,
+    channels: u16,
+    sample_size: u16,
+    sample_rate: u32,
+    max_bitrate: u32,
+    avg_bitrate: u32,
+}
+
+/// Reads the size field of an MPEG-4 descriptor.
+fn read_descriptor_len(reader: &mut impl Read) -> Result<u32> {
+    let mut len = 0;
+    for _ in 0..4 {
+        let [b] = read_array(reader)?;
+        len = (len << 7) | (b & 0x7F) as u32;
+        if b & 0x80 == 0 {
+            break;
+        }
+    }
+    Ok(len)
+}
+
+fn read_esds(reader: &mut impl Read, track: &mut Mp4Track) -> Result<()> {
+    read_array::<4>(reader)?; // version and flags
+    let [tag] = read_array(reader)?;
+    if tag != 0x03 {
+        return Ok(());
+    }
+    read_descriptor_len(reader)?;
+    read_array::<2>(reader)?; // ES_ID
+    let [flags] = read_array(reader)?;
+    if flags & 0x80 != 0 {
+        read_array::<2>(reader)?;
+    }
+    if flags & 0x40 != 0 {
+        let [len] = read_array(reader)?;
+        std::io::copy(&mut reader.take(len as u64), &mut std::io::sink())?;
+    }
+    if flags & 0x20 != 0 {
+        read_array::<2>(reader)?;
+    }
+    let [tag] = read_array(reader)?;
+    if tag != 0x04 {
+        return Ok(());
+    }
+    read_descriptor_len(reader)?;
+    let config: [u8; 13] = read_array(reader)?;
+    track.object_type = config[0];
+    track.max_bitrate = u32::from_be_bytes(config[5..9].try_into().unwrap());
+    track.avg_bitrate = u32::from_be_bytes(config[9..13].try_into().unwrap());
+    Ok(())
+}
+
+fn read_stsd(reader: &mut (impl Read + Seek), len: u64, track: &mut Mp4Track) -> Result<()> {
+    read_array::<8>(reader)?; // version, flags and entry count
+    let (codec, size) = read_atom(reader, len.saturating_sub(8))?;
+    track.codec = Some(codec);
+    if size < 28 {
+        return Ok(());
+    }
+    let entry: [u8; 28] = read_array(reader)?;
+    track.channels = u16::from_be_bytes([entry[16], entry[17]]);
+    track.sample_size = u16::from_be_bytes([entry[18], entry[19]]);
+    track.sample_rate = u16::from_be_bytes([entry[24], entry[25]]) as u32;
+    // QuickTime sound description versions carry extra fields.
+    let extra = match u16::from_be_bytes([entry[8], entry[9]]) {
+        1 => 16,
+        2 => 36,
+        _ => 0,
+    };
+    if size < 28 + extra {
+        return Ok(());
+    }
+    reader.seek(SeekFrom::Current(extra as i64))?;
+    for_each_atom(reader, size - 28 - extra, |reader, kind, _| {
+        if &kind == b"esds" {
+            read_esds(reader, track)?;
+        }
+        Ok(())
+    })
+}
+
+fn read_mp4(reader: &mut (impl Read + Seek)) -> Result<AudioProperties> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+    let mut duration = None;
+    let mut track = None;
+    let mut mdat_len = 0;
+    for_each_atom(reader, file_len, |reader, kind, len| {
+        match &kind {
+            b"mdat" => mdat_len += len,
+            b"moov" => for_each_atom(reader, len, |reader, kind, len| match &kind {
+                b"mvhd" => {
+                    let [version, ..] = read_array::<4>(reader)?;
+                    let (timescale, value) = if version == 1 {
+                        read_array::<16>(reader)?;
+                        (
+                            read_u32_be(reader)?,
+                            u64::from_be_bytes(read_array(reader)?),
+                        )
+                    } else {
+                        read_array::<8>(reader)?;
+                        (read_u32_be(reader)?, read_u32_be(reader)? as u64)
+                    };
+                    if timescale > 0 {
+                        duration = Some(units_to_duration(value as f64, timescale as f64));
+                    }
+                    Ok(())
+                }
+                b"trak" if track.is_none() => {
+                    let mut current = Mp4Track::default();
+                    read_trak(reader, len, &mut current)?;
+                    if current.is_audio {
+                        track = Some(current);
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            })?,
+            _ => (),
+        }
+        Ok(())
+    })?;
+    let track = track.ok_or_else(|| fmt_error("No audio track"))?;
+    let duration = duration.unwrap_or_default();
+    let codec = match &track.codec.unwrap_or_default() {
+        b"mp4a" => match track.object_type {
+            0x69 | 0x6B => "MP3".into(),
+            _ => "AAC".into(),
+        },
+        b"alac" => "ALAC".into(),
+        b"ac-3" => "AC-3".into(),
+        b"ec-3" => "E-AC-3".into(),
+        b"fLaC" => "FLAC".into(),
+        b"Opus" => "Opus".into(),
+        other => String::from_utf8_lossy(other).trim().to_string(),
+    };
+    let lossless = codec == "ALAC" || codec == "FLAC";
+    let bitrate = if track.avg_bitrate > 0 {
+        (track.avg_bitrate as f64 / 1000.0).round() as u32
+    } else {
+        bitrate(mdat_len, duration)
+    };
+    Ok(AudioProperties {
+        duration,
+        bitrate,
+        sample_rate: track.sample_rate,
+        channels: track.channels as u8,
+        bits_per_sample: lossless.then_some(track.sample_size as u8),
+        codec,
+        vbr: lossless || (track.max_bitrate != track.avg_bitrate),
+    })
+}
+
+fn read_trak(reader: &mut (impl Read + Seek), len: u64, track: &mut Mp4Track) -> Result<()> {
+    for_each_atom(reader, len, |reader, kind, len| match &kind {
+        b"mdia" | b"minf" | b"stbl" => read_trak(reader, len, track),
+        b"hdlr" => {
+            let hdlr: [u8; 12] = read_array(reader)?;
+            track.is_audio = &hdlr[8..12] == b"soun";
+            Ok(())
+        }
+        b"stsd" => read_stsd(reader, len, track),
+        _ => Ok(()),
+    })
+}
+
+const MPEG_BITRATES: [[u32; 15]; 5] = [
+    // MPEG-1 layer I, II, III
+    [
+        0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+    ],
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+    ],
+    [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ],
+    // MPEG-2/2.5 layer I, II & III
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+    ],
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+const MPEG_SAMPLE_RATES: [u32; 3] = [44100, 48000, 32000];
+
+struct MpegHeader {
+    /// 1 for MPEG-1, 2 for MPEG-2 and MPEG-2.5
+    version: u8,
+    layer: u8,
+    bitrate: u32,
+    sample_rate: u32,
+    channels: u8,
+    frame_len: u32,
+    samples_per_frame: u32,
+}
+
+impl MpegHeader {
+    fn parse(header: [u8; 4]) -> Option<MpegHeader> {
+        if header[0] != 0xFF || header[1] & 0xE0 != 0xE0 {
+            return None;
+        }
+        let (version, rate_div) = match (header[1] >> 3) & 0x03 {
+            0b11 => (1, 1),
+            0b10 => (2, 2),
+            0b00 => (2, 4),
+            _ => return None,
+        };
+        let layer = match (header[1] >> 1) & 0x03 {
+            0b11 => 1,
+            0b10 => 2,
+            0b01 => 3,
+            _ => return None,
+        };
+        let bitrate_index = (header[2] >> 4) as usize;
+        let rate_index = ((header[2] >> 2) & 0x03) as usize;
+        if bitrate_index == 0 || bitrate_index == 15 || rate_index == 3 {
+            return None;
+        }
+        let table = match (version, layer) {
+            (1, layer) => layer as usize - 1,
+            (_, 1) => 3,
+            _ => 4,
+        };
+        let bitrate = MPEG_BITRATES[table][bitrate_index];
+        let sample_rate = MPEG_SAMPLE_RATES[rate_index] / rate_div;
+        let padding = ((header[2] >> 1) & 0x01) as u32;
+        let channels = if header[3] >> 6 == 0b11 { 1 } else { 2 };
+        let (samples_per_frame, frame_len) = match (version, layer) {
+            (_, 1) => (384, (12 * bitrate * 1000 / sample_rate + padding) * 4),
+            (2, 3) => (576, 72 * bitrate * 1000 / sample_rate + padding),
+            _ => (1152, 144 * bitrate * 1000 / sample_rate + padding),
+        };
+        Some(MpegHeader {
+            version,
+            layer,
+            bitrate,
+            sample_rate,
+            channels,
+            frame_len,
+            samples_per_frame,
+        })
+    }
+}
+
+/// Finds the first MPEG frame whose successor also starts with a valid
+/// header, returning its offset and header.
+fn find_mpeg_frame(buf: &[u8]) -> Option<(usize, MpegHeader)> {
+    (0..buf.len().saturating_sub(4)).find_map(|i| {
+        let header = MpegHeader::parse(buf[i..i + 4].try_into().unwrap())?;
+        let next = i + header.frame_len as usize;
+        match buf.get(next..next + 4) {
+            Some(next) if MpegHeader::parse(next.try_into().unwrap()).is_none() => None,
+            _ => Some((i, header)),
+        }
+    })
+}
+
+fn read_mpeg(reader: &mut (impl Read + Seek)) -> Result<AudioProperties> {
+    skip_id3v2(reader)?;
+    let start = reader.stream_position()?;
+    let mut end = reader.seek(SeekFrom::End(0))?;
+    if end >= start + 128 {
+        reader.seek(SeekFrom::End(-128))?;
+        if &read_array::<3>(reader)? == b"TAG" {
+            end -= 128;
+        }
+    }
+    reader.seek(SeekFrom::Start(start))?;
+    let mut buf = Vec::new();
+    reader.take(64 * 1024).read_to_end(&mut buf)?;
+    let (offset, header) = find_mpeg_frame(&buf).ok_or_else(|| fmt_error("No MPEG frame"))?;
+    let frame = &buf[offset..];
+    let side_info = match (header.version, header.channels) {
+        (1, 1) => 17,
+        (1, _) => 32,
+        (_, 1) => 9,
+        _ => 17,
+    };
+    // (frame count, byte count, vbr)
+    let mut vbr_info = None;
+    let xing = frame.get(4 + side_info..).unwrap_or_default();
+    if xing.starts_with(b"Xing") || xing.starts_with(b"Info") {
+        let flags = xing
+            .get(4..8)
+            .ok_or_else(|| fmt_error("Truncated Xing header"))?;
+        let flags = u32::from_be_bytes(flags.try_into().unwrap());
+        let mut fields = xing[8..]
+            .chunks_exact(4)
+            .map(|c| u32::from_be_bytes(c.try_into().unwrap()));
+        let frames = if flags & 0x01 != 0 {
+            fields.next()
+        } else {
+            None
+        };
+        let bytes = if flags & 0x02 != 0 {
+            fields.next()
+        } else {
+            None
+        };
+        vbr_info = Some((frames, bytes, xing.starts_with(b"Xing")));
+    } else if frame.get(36..40) == Some(b"VBRI") && frame.len() >= 54 {
+        let bytes = u32::from_be_bytes(frame[46..50].try_into().unwrap());
+        let frames = u32::from_be_bytes(frame[50..54].try_into().unwrap());
+        vbr_info = Some((Some(frames), Some(bytes), true));
+    }
+    let audio_len = end.saturating_sub(start + offset as u64);
+    let (duration, bitrate, vbr) = match vbr_info {
+        Some((Some(frames), bytes, vbr)) => {
+            let duration = units_to_duration(
+                frames as f64 * header.samples_per_frame as f64,
+                header.sample_rate as f64,
+            );
+            let bytes = bytes.map_or(audio_len, |b| b as u64);
+            (duration, self::bitrate(bytes, duration), vbr)
+        }
+        vbr_info => {
+            let duration =
+                units_to_duration(audio_len as f64 * 8.0, header.bitrate as f64 * 1000.0);
+            let vbr = vbr_info.is_some_and(|v| v.2);
+            (duration, header.bitrate, vbr)
+        }
+    };
+    Ok(AudioProperties {
+        duration,
+        bitrate,
+        sample_rate: header.sample_rate,
+        channels: header.channels,
+        bits_per_sample: None,
+        codec: format!("MP{}", header.layer),
+        vbr,
+    })
+}
+
+fn read_ogg(reader: &mut (impl Read + Seek)) -> Result<AudioProperties> {
+    let header: [u8; 27] = read_array(reader)?;
+    if &header[0..4] != b"OggS" {
+        return Err(fmt_error("Invalid Ogg page"));
+    }
+    let serial = u32::from_le_bytes(header[14..18].try_into().unwrap());
+    let segments = header[26] as usize;
+    let lacing = {
+        let mut lacing = vec![0; segments];
+        reader.read_exact(&mut lacing)?;
+        lacing
+    };
+    let mut packet = vec![0; lacing.iter().map(|&l| l as usize).sum()];
+    reader.read_exact(&mut packet)?;
+    // (properties, granule rate, granules to skip, header packets)
+    let (mut properties, rate, pre_skip, header_packets) =
+        if packet.starts_with(b"\x01vorbis") && packet.len() >= 28 {
+            let u32_at = |i: usize| u32::from_le_bytes(packet[i..i + 4].try_into().unwrap());
+            let sample_rate = u32_at(12);
+            let (max, nominal, min) = (u32_at(16) as i32, u32_at(20) as i32, u32_at(24) as i32);
+            let properties = AudioProperties {
+                sample_rate,
+                channels: packet[11],
+                bitrate: (nominal.max(0) as f64 / 1000.0).round() as u32,
+                codec: "Vorbis".into(),
+                vbr: !(max == nominal && nominal == min && nominal > 0),
+                ..Default::default()
+            };
+            (properties, sample_rate, 0, 3)
+        } else if packet.starts_with(b"OpusHead") && packet.len() >= 16 {
+            let properties = AudioProperties {
+                sample_rate: u32::from_le_bytes(packet[12..16].try_into().unwrap()),
+                channels: packet[9],
+                codec: "Opus".into(),
+                vbr: true,
+                ..Default::default()
+            };
+            let pre_skip = u16::from_le_bytes([packet[10], packet[11]]) as u64;
+            // Opus granule positions always count 48 kHz samples.
+            (properties, 48000, pre_skip, 2)
+        } else if packet.starts_with(b"\x7FFLAC") && packet.len() >= 51 {
+            let properties = parse_streaminfo(packet[17..51].try_into().unwrap());
+            let rate = properties.sample_rate;
+            let headers = u16::from_be_bytes([packet[7], packet[8]]) as u32;
+            (properties, rate, 0, 1 + headers)
+        } else {
+            return Err(Error::NotSupportedError);
+        };
+    let audio_start = audio_start(reader, serial, header_packets)?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    let granule = last_granule(reader, end, serial)?;
+    let samples = granule.saturating_sub(pre_skip);
+    properties.duration = units_to_duration(samples as f64, rate as f64);
+    if properties.bitrate == 0 || properties.vbr {
+        let computed = bitrate(end.saturating_sub(audio_start), properties.duration);
+        if computed > 0 {
+            properties.bitrate = computed;
+        }
+    }
+    Ok(properties)
+}
+
+/// Finds the end of the page on which the stream `serial` finishes its first
+/// `packets` packets, where the audio starts.
+fn audio_start(reader: &mut (impl Read + Seek), serial: u32, packets: u32) -> Result<u64> {
+    reader.seek(SeekFrom::Start(0))?;
+    let mut finished = 0;
+    while finished < packets {
+        let header: [u8; 27] = read_array(reader)?;
+        if &header[0..4] != b"OggS" {
+            return Err(fmt_error("Invalid Ogg page"));
+        }
+        let mut lacing = vec![0; header[26] as usize];
+        reader.read_exact(&mut lacing)?;
+        if u32::from_le_bytes(header[14..18].try_into().unwrap()) == serial {
+            finished += lacing.iter().filter(|&&l| l < 255).count() as u32;
+        }
+        let len = lacing.iter().map(|&l| l as i64).sum();
+        reader.seek(SeekFrom::Current(len))?;
+    }
+    Ok(reader.stream_position()?)
+}
+
+/// Finds the granule position of the last page of the stream `serial`.
+fn last_granule(reader: &mut (impl Read + Seek), end: u64, serial: u32) -> Result<u64> {
+    let mut chunk_end = end;
+    while chunk_end > 0 {
+        let start = chunk_end.saturating_sub(64 * 1024);
+        reader.seek(SeekFrom::Start(start))?;
+        // Overlap the previous chunk by a page header so none is split.
+        let len = (end.min(chunk_end + 27) - start) as usize;
+        let mut buf = vec![0; len];
+        reader.read_exact(&mut buf)?;
+        for i in (0..buf.len().saturating_sub(26)).rev() {
+            if &buf[i..i + 4] == b"OggS"
+                && u32::from_le_bytes(buf[i + 14..i + 18].try_into().unwrap()) == serial
+            {
+                let granule = u64::from_le_bytes(buf[i + 6..i + 14].try_into().unwrap());
+                if granule != u64::MAX {
+                    return Ok(granule);
+                }
+            }
+        }
+        chunk_end = start;
+    }
+    Err(fmt_error("No Ogg page with a granule position"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// MPEG-1 Layer III, 128 kbit/s, 44.1 kHz, stereo: 417 byte frames.
+    const HEADER: [u8; 4] = [0xFF, 0xFB, 0x90, 0x00];
+
+    fn frame(payload: &[u8]) -> Vec<u8> {
+        let mut frame = HEADER.to_vec();
+        frame.resize(4 + 32, 0);
+        frame.extend_from_slice(payload);
+        frame.resize(417, 0);
+        frame
+    }
+
+    fn read(data: Vec<u8>) -> Result<AudioProperties> {
+        AudioProperties::read_from(&mut Cursor::new(data), MusicFormat::Mp3)
+    }
+
+    #[test]
+    fn reads_cbr_mpeg() {
+        let props = read(frame(&[]).repeat(100)).unwrap();
+        assert_eq!(props.codec, "MP3");
+        assert_eq!(
+            (props.sample_rate, props.channels, props.bitrate),
+            (44100, 2, 128)
+        );
+        assert_eq!(props.duration.as_millis(), 41700 * 8 / 128);
+        assert!(!props.vbr);
+    }
+
+    #[test]
+    fn reads_xing_frame_count() {
+        let mut xing = b"Xing".to_vec();
+        xing.extend_from_slice(&1u32.to_be_bytes());
+        xing.extend_from_slice(&1000u32.to_be_bytes());
+        let mut data = frame(&xing);
+        data.extend(frame(&[]).repeat(9));
+        let props = read(data).unwrap();
+        assert_eq!(props.duration.as_millis(), 1000 * 1152 * 1000 / 44100);
+        assert!(props.vbr);
+    }
+
+    #[test]
+    fn rejects_truncated_xing() {
+        let mut data = HEADER.to_vec();
+        data.resize(4 + 32, 0);
+        data.extend_from_slice(b"Xing\0\0");
+        assert!(matches!(read(data), Err(Error::FmtError(_))));
+    }
+
+    #[test]
+    fn reads_vbri_frame_count() {
+        let mut vbri = b"VBRI".to_vec();
+        vbri.extend_from_slice(&[0, 1, 0, 0, 0, 50]); // version, delay, quality
+        vbri.extend_from_slice(&100_000u32.to_be_bytes());
+        vbri.extend_from_slice(&500u32.to_be_bytes());
+        let mut data = frame(&vbri);
+        data.extend(frame(&[]).repeat(9));
+        let props = read(data).unwrap();
+        let duration = units_to_duration(500.0 * 1152.0, 44100.0);
+        assert_eq!(props.duration, duration);
+        assert_eq!(props.bitrate, bitrate(100_000, duration));
+        assert!(props.vbr);
+    }
+
+    /// A STREAMINFO block for 16 bit samples.
+    fn streaminfo(sample_rate: u32, channels: u8, samples: u64) -> [u8; 34] {
+        let mut info = [0; 34];
+        let packed =
+            (sample_rate as u64) << 44 | ((channels - 1) as u64) << 41 | 15 << 36 | samples;
+        info[10..18].copy_from_slice(&packed.to_be_bytes());
+        info
+    }
+
+    #[test]
+    fn reads_flac_streaminfo() {
+        let mut data = b"fLaC".to_vec();
+        data.extend_from_slice(&[0x00, 0, 0, 34]);
+        data.extend_from_slice(&streaminfo(44100, 2, 441_000));
+        // A last PADDING block, then 10 s of "audio".
+        data.extend_from_slice(&[0x81, 0, 0, 4, 0, 0, 0, 0]);
+        data.resize(data.len() + 50_000, 0);
+        let props = AudioProperties::read_from(&mut Cursor::new(data), MusicFormat::Flac).unwrap();
+        assert_eq!(props.codec, "FLAC");
+        assert_eq!((props.sample_rate, props.channels), (44100, 2));
+        assert_eq!(props.bits_per_sample, Some(16));
+        assert_eq!(props.duration, Duration::from_secs(10));
+        assert_eq!(props.bitrate, 40);
+    }
+
+    fn atom(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut atom = (8 + content.len() as u32).to_be_bytes().to_vec();
+        atom.extend_from_slice(kind);
+        atom.extend_from_slice(content);
+        atom
+    }
+
+    /// An MP4 file with `mvhd` and one audio track described by `entry`.
+    fn mp4(mvhd: &[u8], entry: &[u8]) -> Vec<u8> {
+        let mut hdlr = [0; 24];
+        hdlr[8..12].copy_from_slice(b"soun");
+        let mut stsd = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stsd.extend_from_slice(entry);
+        let stbl = atom(b"stbl", &atom(b"stsd", &stsd));
+        let mdia = [atom(b"hdlr", &hdlr), atom(b"minf", &stbl)].concat();
+        let trak = atom(b"trak", &atom(b"mdia", &mdia));
+        let moov = atom(b"moov", &[atom(b"mvhd", mvhd), trak].concat());
+        [
+            atom(b"ftyp", b"M4A \0\0\0\0"),
+            moov,
+            atom(b"mdat", &[1; 1000]),
+        ]
+        .concat()
+    }
+
+    /// An `mp4a` sample entry, optionally with an `esds` carrying bitrates.
+    fn mp4a(bitrates: Option<(u32, u32)>) -> Vec<u8> {
+        let mut entry = [0; 28];
+        entry[17] = 2; // channels
+        entry[19] = 16; // sample size
+        entry[24..26].copy_from_slice(&44100u16.to_be_bytes());
+        let mut entry = entry.to_vec();
+        if let Some((max, avg)) = bitrates {
+            let mut config = vec![0x40, 0x15, 0, 0, 0];
+            config.extend_from_slice(&max.to_be_bytes());
+            config.extend_from_slice(&avg.to_be_bytes());
+            let mut esds = vec![0, 0, 0, 0, 0x03, 3 + 2 + config.len() as u8, 0, 1, 0];
+            esds.extend_from_slice(&[0x04, config.len() as u8]);
+            esds.extend_from_slice(&config);
+            entry.extend(atom(b"esds", &esds));
+        }
+        atom(b"mp4a", &entry)
+    }
+
+    fn read_mp4_data(data: Vec<u8>) -> Result<AudioProperties> {
+        AudioProperties::read_from(&mut Cursor::new(data), MusicFormat::M4a)
+    }
+
+    #[test]
+    fn reads_mp4_headers() {
+        // Version 0: 32 bit times, 1000 units per second.
+        let mut mvhd = vec![0; 12];
+        mvhd.extend_from_slice(&1000u32.to_be_bytes());
+        mvhd.extend_from_slice(&2500u32.to_be_bytes());
+        let props = read_mp4_data(mp4(&mvhd, &mp4a(Some((160_000, 128_000))))).unwrap();
+        assert_eq!(props.codec, "AAC");
+        assert_eq!((props.sample_rate, props.channels), (44100, 2));
+        assert_eq!(props.duration, Duration::from_millis(2500));
+        assert_eq!(props.bitrate, 128);
+        assert!(props.vbr);
+
+        // Version 1: 64 bit times.
+        let mut mvhd = vec![1, 0, 0, 0];
+        mvhd.extend_from_slice(&[0; 16]);
+        mvhd.extend_from_slice(&48000u32.to_be_bytes());
+        mvhd.extend_from_slice(&(48000u64 * 8).to_be_bytes());
+        let props = read_mp4_data(mp4(&mvhd, &mp4a(None))).unwrap();
+        assert_eq!(props.duration, Duration::from_secs(8));
+        // Without an esds, the bitrate comes from the mdat size.
+        assert_eq!(props.bitrate, 1);
+        assert!(!props.vbr);
+    }
+
+    #[test]
+    fn ignores_short_sample_entry() {
+        let mut mvhd = vec![0; 12];
+        mvhd.extend_from_slice(&1000u32.to_be_bytes());
+        mvhd.extend_from_slice(&1000u32.to_be_bytes());
+        // The entry is only a header; the mdat that follows is not read as
+        // its fields.
+        let props = read_mp4_data(mp4(&mvhd, &atom(b"mp4a", &[]))).unwrap();
+        assert_eq!(props.codec, "AAC");
+        assert_eq!((props.sample_rate, props.channels), (0, 0));
+    }
+
+    /// An Ogg page of the stream 1 holding whole `packets`.
+    fn ogg_page(header_type: u8, granule: u64, packets: &[&[u8]]) -> Vec<u8> {
+        let mut lacing = Vec::new();
+        for packet in packets {
+            lacing.extend(std::iter::repeat_n(255, packet.len() / 255));
+            lacing.push((packet.len() % 255) as u8);
+        }
+        let mut page = b"OggS\0".to_vec();
+        page.push(header_type);
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&1u32.to_le_bytes());
+        page.extend_from_slice(&[0; 8]); // sequence and checksum
+        page.push(lacing.len() as u8);
+        page.extend_from_slice(&lacing);
+        page.extend(packets.concat());
+        page
+    }
+
+    fn read_ogg_data(data: Vec<u8>) -> Result<AudioProperties> {
+        AudioProperties::read_from(&mut Cursor::new(data), MusicFormat::Ogg)
+    }
+
+    #[test]
+    fn reads_vorbis_granule() {
+        let mut identification = b"\x01vorbis\0\0\0\0\x02".to_vec();
+        identification.extend_from_slice(&44100u32.to_le_bytes());
+        identification.extend_from_slice(&[0; 12]);
+        identification.extend_from_slice(&[0xb8, 1]);
+        let mut data = ogg_page(0x02, 0, &[&identification]);
+        let comments = [b"\x03vorbis".as_slice(), &[0; 4000]].concat();
+        data.extend(ogg_page(0, 0, &[&comments, b"\x05vorbis"]));
+        let header_len = data.len();
+        data.extend(ogg_page(0, 44100, &[&[0; 3000]]));
+        data.extend(ogg_page(0x04, 88200, &[&[0; 3000]]));
+        let props = read_ogg_data(data.clone()).unwrap();
+        assert_eq!(props.codec, "Vorbis");
+        assert_eq!((props.sample_rate, props.channels), (44100, 2));
+        assert_eq!(props.duration, Duration::from_secs(2));
+        // The large comment header does not count towards the bitrate.
+        let audio = (data.len() - header_len) as u64;
+        assert_eq!(props.bitrate, bitrate(audio, props.duration));
+        assert!(props.vbr);
+    }
+
+    #[test]
+    fn reads_opus_pre_skip() {
+        let mut head = b"OpusHead\x01\x02".to_vec();
+        head.extend_from_slice(&312u16.to_le_bytes());
+        head.extend_from_slice(&44100u32.to_le_bytes());
+        head.extend_from_slice(&[0, 0, 0]);
+        let mut data = ogg_page(0x02, 0, &[&head]);
+        data.extend(ogg_page(0, 0, &[b"OpusTags\0\0\0\0\0\0\0\0"]));
+        let header_len = data.len();
+        data.extend(ogg_page(0x04, 48312 * 3, &[&[0; 1000]]));
+        let props = read_ogg_data(data.clone()).unwrap();
+        assert_eq!(props.codec, "Opus");
+        assert_eq!((props.sample_rate, props.channels), (44100, 2));
+        // Granules count 48 kHz samples after the pre-skip.
+        let duration = units_to_duration((48312 * 3 - 312) as f64, 48000.0);
+        assert_eq!(props.duration, duration);
+        let audio = (data.len() - header_len) as u64;
+        assert_eq!(props.bitrate, bitrate(audio, duration));
+    }
+
+    #[test]
+    fn guards_durations() {
+        assert_eq!(parse_streaminfo(&[0; 34]).duration, Duration::ZERO);
+        assert_eq!(units_to_duration(1.0, 0.0), Duration::ZERO);
+        assert_eq!(units_to_duration(f64::MAX, 1.0), Duration::ZERO);
+        assert_eq!(units_to_duration(3.0, 2.0), Duration::from_millis(1500));
+    }
+}
@@ -0,0 +1,340 @@
+//! Files stored next to the music file, such as `Song.lrc` for `Song.flac`
+//! or the `cover.jpg` of its album.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use super::{Artwork, ImgFmt, MusicTag, PictureType};
+use crate::{
+    lyrics::{decode, Lyrics},
+    Error, Result,
+};
+
+/// Sidecar lyrics extensions, preferred first.
+const LYRICS_EXTENSIONS: [&str; 2] = ["lrc", "txt"];
+const IMAGE_EXTENSIONS: [&str; 7] = ["jpg", "jpeg", "png", "webp", "gif", "bmp", "tiff"];
+/// Names of album covers shared by every track in a directory, preferred
+/// first.
+const COVER_NAMES: [&str; 3] = ["cover", "folder", "front"];
+
+/// The directory of `path`, `.` for a bare file name.
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+/// Files in `dir` other than `except` whose stem is one of `stems` and whose
+/// extension is one of `extensions`, both compared case-insensitively and
+/// sorted by the order of `stems`, then of `extensions`.
+fn find_files(
+    dir: &Path,
+    except: &Path,
+    stems: &[&str],
+    extensions: &[&str],
+) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let candidate = entry?.path();
+        if candidate == except || !candidate.is_file() {
+            continue;
+        }
+        let (Some(stem), Some(ext)) = (
+            candidate.file_stem().and_then(|s| s.to_str()),
+            candidate.extension().and_then(|e| e.to_str()),
+        ) else {
+            continue;
+        };
+        let stem_rank = stems.iter().position(|s| s.eq_ignore_ascii_case(stem));
+        let ext_rank = extensions.iter().position(|e| e.eq_ignore_ascii_case(ext));
+        if let (Some(stem_rank), Some(ext_rank)) = (stem_rank, ext_rank) {
+            found.push((stem_rank, ext_rank, candidate));
+        }
+    }
+    found.sort();
+    Ok(found.into_iter().map(|(_, _, path)| path).collect())
+}
+
+/// Files next to `path` with the same stem and one of `extensions`.
+fn find_sidecars(path: &Path, extensions: &[&str]) -> Result<Vec<PathBuf>> {
+    match path.file_stem().and_then(|s| s.to_str()) {
+        Some(stem) => find_files(parent_dir(path), path, &[stem], extensions),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// The name an exported picture is saved under.
+fn artwork_stem(picture_type: PictureType) -> &'static str {
+    match picture_type {
+        PictureType::CoverFront => "cover",
+        PictureType::CoverBack => "back",
+        PictureType::Leaflet => "leaflet",
+        PictureType::Media => "media",
+        PictureType::Icon | PictureType::OtherIcon => "icon",
+        PictureType::LeadArtist | PictureType::Artist => "artist",
+        PictureType::Conductor => "conductor",
+        PictureType::Band => "band",
+        PictureType::Composer => "composer",
+        PictureType::Lyricist => "lyricist",
+        PictureType::RecordingLocation => "location",
+        PictureType::DuringRecording => "recording",
+        PictureType::DuringPerformance => "performance",
+        PictureType::ScreenCapture => "screenshot",
+        PictureType::Illustration => "illustration",
+        PictureType::BandLogo => "band-logo",
+        PictureType::PublisherLogo => "publisher-logo",
+        PictureType::BrightFish | PictureType::Other => "other",
+    }
+}
+
+impl MusicTag {
+    /// `.lrc` and `.txt` files next to the music file with the same name,
+    /// `.lrc` first.
+    pub fn sidecar_lyrics_paths(&self) -> Result<Vec<PathBuf>> {
+        match self.as_path() {
+            Some(path) => find_sidecars(path, &LYRICS_EXTENSIONS),
+            None => Ok(Vec::new()),
+        }
+    }
+    fn read_sidecar_lyrics(&self) -> Result<Option<String>> {
+        match self.sidecar_lyrics_paths()?.first() {
+            Some(path) => Ok(Some(decode(&fs::read(path)?).0)),
+            None => Ok(None),
+        }
+    }
+    /// The first sidecar lyrics file, see [`MusicTag::sidecar_lyrics_paths`].
+    pub fn sidecar_lyrics(&self) -> Result<Option<Lyrics>> {
+        Ok(self.read_sidecar_lyrics()?.map(Lyrics::from))
+    }
+    /// Replaces the lyrics of the tag with the sidecar lyrics file, if there
+    /// is one. Call [`MusicTag::write_to_path`] to save them.
+    pub fn embed_sidecar_lyrics(&mut self) -> Result<bool> {
+        match self.read_sidecar_lyrics()? {
+            Some(lyrics) => {
+                self.set_lyrics(lyrics);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+    /// Writes the first lyrics entry next to the music file, as `.lrc` when
+    /// it has timestamps and `.txt` otherwise. Once written, sidecar lyrics
+    /// with the other extension are removed, so a stale `.lrc` cannot shadow
+    /// a new `.txt`. Returns the path written, or `None` without lyrics.
+    pub fn extract_lyrics_to_sidecar(&self) -> Result<Option<PathBuf>> {
+        let path = self
+            .as_path()
+            .ok_or_else(|| Error::FmtError("MusicTag has no path".into()))?;
+        let Some(entry) = self.lyrics_entries().first() else {
+            return Ok(None);
+        };
+        let ext = match entry.lyrics().timed_lines().is_empty() {
+            true => "txt",
+            false => "lrc",
+        };
+        let sidecar = path.with_extension(ext);
+        fs::write(&sidecar, &entry.text)?;
+        // Only after the write succeeded, so a failure keeps the old lyrics.
+        for stale in find_sidecars(path, &LYRICS_EXTENSIONS)? {
+            let same_ext = stale
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case(ext));
+            if !same_ext {
+                fs::remove_file(stale)?;
+            }
+        }
+        Ok(Some(sidecar))
+    }
+    /// Images next to the music file named like it, such as `Song.jpg`,
+    /// followed by album covers named `cover`, `folder` or `front`.
+    pub fn sidecar_cover_paths(&self) -> Result<Vec<PathBuf>> {
+        let Some(path) = self.as_path() else {
+            return Ok(Vec::new());
+        };
+        let mut paths = find_sidecars(path, &IMAGE_EXTENSIONS)?;
+        for cover in find_files(parent_dir(path), path, &COVER_NAMES, &IMAGE_EXTENSIONS)? {
+            // A track may itself be named `cover`.
+            if !paths.contains(&cover) {
+                paths.push(cover);
+            }
+        }
+        Ok(paths)
+    }
+    /// The images of [`MusicTag::sidecar_cover_paths`] as front covers,
+    /// skipping files that are not images.
+    pub fn sidecar_covers(&self) -> Result<Vec<Artwork>> {
+        let mut artworks = Vec::new();
+        for path in self.sidecar_cover_paths()? {
+            let data = fs::read(path)?;
+            if let Ok(size) = imagesize::blob_size(&data) {
+                artworks.push(Artwork {
+                    height: size.height,
+                    width: size.width,
+                    fmt: ImgFmt::detect(&data),
+                    data,
+                    picture_type: PictureType::CoverFront,
+                    description: String::new(),
+                })
+            }
+        }
+        Ok(artworks)
+    }
+    /// Writes every artwork into `dir`, created if missing, named after its
+    /// picture type with the extension of its format: `cover.jpg`,
+    /// `back.png`. Existing files are kept: a name already taken gets the
+    /// next free number, as in `cover-2.jpg`. Returns the paths written.
+    pub fn extract_artworks(&self, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir.as_ref())?;
+        let mut written: Vec<PathBuf> = Vec::new();
+        for artwork in self.artworks() {
+            let stem = artwork_stem(artwork.picture_type);
+            let ext = artwork.fmt.extension();
+            let mut path = dir.as_ref().join(format!("{}.{}", stem, ext));
+            let mut n = 1;
+            // Creating the file fails if it exists, so files made by another
+            // process meanwhile are not overwritten.
+            let mut file = loop {
+                match OpenOptions::new().write(true).create_new(true).open(&path) {
+                    Ok(file) => break file,
+                    Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                        n += 1;
+                        path = dir.as_ref().join(format!("{}-{}.{}", stem, n, ext));
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            };
+            file.write_all(&artwork.data)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory for `name` with an MP3 file `Song.mp3` in it.
+    fn temp_song(name: &str) -> (PathBuf, MusicTag) {
+        let dir = std::env::temp_dir().join(format!("music-tag-sidecar-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Song.mp3");
+        let mut frame = vec![0xFF, 0xFB, 0x90, 0x00];
+        frame.resize(417, 0);
+        fs::write(&path, frame.repeat(10)).unwrap();
+        let mut tag = id3::Tag::new();
+        id3::TagLike::set_title(&mut tag, "Song");
+        tag.write_to_path(&path, id3::Version::Id3v24).unwrap();
+        (dir, MusicTag::read_from_path(&path).unwrap())
+    }
+
+    /// A 3x2 PNG.
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x03\0\0\0\x02\x08\x02\0\0\0";
+
+    #[test]
+    fn discovers_covers_in_order() {
+        let (dir, tag) = temp_song("covers");
+        for name in [
+            "front.png",
+            "Folder.JPG",
+            "cover.gif",
+            "cover.png",
+            "Song.png",
+            "back.png",
+        ] {
+            fs::write(dir.join(name), PNG).unwrap();
+        }
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        let names: Vec<_> = tag
+            .sidecar_cover_paths()
+            .unwrap()
+            .into_iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "Song.png",
+                "cover.png",
+                "cover.gif",
+                "Folder.JPG",
+                "front.png"
+            ]
+        );
+        let covers = tag.sidecar_covers().unwrap();
+        assert_eq!(covers.len(), 5);
+        assert!(covers.iter().all(|c| (c.width, c.height) == (3, 2)));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn numbers_extracted_artworks() {
+        let (dir, mut tag) = temp_song("extract");
+        tag.set_artworks(vec![(PNG, ImgFmt::PNG), (PNG, ImgFmt::PNG)])
+            .unwrap();
+        let mut back = tag.artworks().next().unwrap().clone();
+        back.picture_type = PictureType::CoverBack;
+        tag.add_artwork(back);
+        let out = dir.join("art").join("nested");
+        let names = |paths: Vec<PathBuf>| -> Vec<String> {
+            paths
+                .iter()
+                .map(|path| {
+                    path.strip_prefix(&out)
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect()
+        };
+        let written = tag.extract_artworks(&out).unwrap();
+        assert_eq!(names(written), ["cover.png", "cover-2.png", "back.png"]);
+        // Files from an earlier run are kept.
+        let written = tag.extract_artworks(&out).unwrap();
+        assert_eq!(names(written), ["cover-3.png", "cover-4.png", "back-2.png"]);
+        assert_eq!(fs::read(out.join("cover.png")).unwrap(), PNG);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn replaces_stale_lyrics_sidecars() {
+        let (dir, mut tag) = temp_song("lyrics");
+        fs::write(dir.join("Song.lrc"), "[00:01.00]old").unwrap();
+        fs::write(dir.join("Other.txt"), "other").unwrap();
+        tag.set_lyrics("plain words");
+        let written = tag.extract_lyrics_to_sidecar().unwrap().unwrap();
+        assert_eq!(written, dir.join("Song.txt"));
+        assert_eq!(tag.sidecar_lyrics_paths().unwrap(), [written]);
+        assert_eq!(
+            tag.sidecar_lyrics().unwrap().unwrap().lines(),
+            &["plain words"]
+        );
+        assert!(dir.join("Other.txt").exists());
+
+        tag.set_lyrics("[00:02.00]new");
+        let written = tag.extract_lyrics_to_sidecar().unwrap().unwrap();
+        assert_eq!(tag.sidecar_lyrics_paths().unwrap(), [dir.join("Song.lrc")]);
+        assert_eq!(fs::read_to_string(written).unwrap(), "[00:02.00]new");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_lyrics_sidecar_when_write_fails() {
+        let (dir, mut tag) = temp_song("lyrics-failed");
+        fs::write(dir.join("Song.lrc"), "[00:01.00]old").unwrap();
+        fs::create_dir(dir.join("Song.txt")).unwrap();
+        tag.set_lyrics("plain words");
+        assert!(tag.extract_lyrics_to_sidecar().is_err());
+        assert_eq!(
+            fs::read_to_string(dir.join("Song.lrc")).unwrap(),
+            "[00:01.00]old"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt::Display;
 
 pub type Result<T> = std::result::Result<T, Error>;
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub enum Error {
     Id3Error(id3::Error),
     FlacError(metaflac::Error),
@@ -11,14 +11,15 @@ pub enum Error {
     DecoderError(base64::DecodeError),
     OtherError(symphonia::core::errors::Error),
     ImageError(imagesize::ImageError),
+    StreamError(rodio::StreamError),
+    PlayError(rodio::PlayError),
+    WavError(hound::Error),
+    #[cfg(feature = "image")]
+    CodecError(image::ImageError),
     NotSupportedError,
+    #[default]
     UnknownError,
 }
-impl Default for Error {
-    fn default() -> Self {
-        Self::UnknownError
-    }
-}
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -31,6 +32,11 @@ impl Display for Error {
             Error::OtherError(err) => Display::fmt(err, f),
             Error::UnknownError => f.write_str("Unknown Error"),
             Error::ImageError(error) => Display::fmt(error, f),
+            Error::StreamError(error) => Display::fmt(error, f),
+            Error::PlayError(error) => Display::fmt(error, f),
+            Error::WavError(error) => Display::fmt(error, f),
+            #[cfg(feature = "image")]
+            Error::CodecError(error) => Display::fmt(error, f),
             Error::NotSupportedError => f.write_str("Not Supported"),
         }
     }
@@ -76,4 +82,28 @@ impl From<imagesize::ImageError> for Error {
     fn from(value: imagesize::ImageError) -> Self {
         Self::ImageError(value)
     }
+}
+#[cfg(feature = "image")]
+impl From<image::ImageError> for Error {
+    fn from(value: image::ImageError) -> Self {
+        Self::CodecError(value)
+    }
+}
+impl From<rodio::StreamError> for Error {
+    fn from(value: rodio::StreamError) -> Self {
+        Self::StreamError(value)
+    }
+}
+impl From<rodio::PlayError> for Error {
+    fn from(value: rodio::PlayError) -> Self {
+        Self::PlayError(value)
+    }
+}
+impl From<hound::Error> for Error {
+    fn from(value: hound::Error) -> Self {
+        match value {
+            hound::Error::IoError(err) => Error::IoError(err),
+            _ => Error::WavError(value),
+        }
+    }
 }
\ No newline at end of file
@@ -3,6 +3,7 @@ pub mod audio;
 pub mod error;
 pub use error::*;
 
+pub use encoding_rs;
 pub use imagesize;
 use std::io::{ErrorKind, SeekFrom};
 use symphonia::core::io::MediaSource;
@@ -36,54 +37,29 @@ impl AudioReader {
 }
 impl std::io::Seek for AudioReader {
     fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
-        match pos {
-            SeekFrom::Current(i) => {
-                let pos = i as usize + self.pos;
-                if self.buf.len() > pos {
-                    self.pos = pos;
-                    Ok(self.pos as u64)
-                } else {
-                    Err(std::io::Error::new(
-                        ErrorKind::InvalidInput,
-                        "Invalid position",
-                    ))
-                }
-            }
-            SeekFrom::End(e) => {
-                if self.buf.len() >= e as usize {
-                    self.pos = self.buf.len() - e as usize;
-                    Ok(self.pos as u64)
-                } else {
-                    Err(std::io::Error::new(
-                        ErrorKind::InvalidInput,
-                        "Invalid position",
-                    ))
-                }
-            }
-            SeekFrom::Start(s) => {
-                if self.buf.len() >= s as usize {
-                    self.pos = s as usize;
-                    Ok(self.pos as u64)
-                } else {
-                    Err(std::io::Error::new(
-                        ErrorKind::InvalidInput,
-                        "Invalid position",
-                    ))
-                }
+        let (base, offset) = match pos {
+            SeekFrom::Start(s) => (0, s as i64),
+            SeekFrom::Current(i) => (self.pos as i64, i),
+            SeekFrom::End(e) => (self.buf.len() as i64, e),
+        };
+        match base.checked_add(offset) {
+            Some(pos) if pos >= 0 => {
+                self.pos = pos as usize;
+                Ok(self.pos as u64)
             }
+            _ => Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "Invalid position",
+            )),
         }
     }
 }
 impl std::io::Read for AudioReader {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let pos = self.pos;
-        for i in self.pos..self.buf.len() {
-            if i - pos >= buf.len() {
-                break;
-            }
-            buf[i - pos] = self.buf[i];
-            self.pos = i + 1;
-        }
-        Ok(self.pos - pos)
+        let remain = self.buf.get(self.pos..).unwrap_or_default();
+        let len = remain.len().min(buf.len());
+        buf[..len].copy_from_slice(&remain[..len]);
+        self.pos += len;
+        Ok(len)
     }
 }
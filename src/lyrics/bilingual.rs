@@ -0,0 +1,131 @@
+use super::{Lyrics, LyricsDuration, LyricsLine};
+
+/// Timed lines sharing a timestamp: the original line followed by its
+/// translations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineGroup<'a> {
+    pub time: LyricsDuration,
+    pub original: &'a str,
+    pub translations: Vec<&'a str>,
+}
+
+impl Lyrics {
+    /// Groups the timed lines sharing a timestamp. In bilingual LRC files the
+    /// first line of a group is the original and the others are translations.
+    pub fn groups(&self) -> impl Iterator<Item = LineGroup<'_>> {
+        self.timed_lines
+            .chunk_by(|a, b| a.time == b.time)
+            .map(|lines| LineGroup {
+                time: lines[0].time,
+                original: &lines[0].text,
+                translations: lines[1..].iter().map(|line| line.text.as_str()).collect(),
+            })
+    }
+    /// Whether any timestamp has more than one line.
+    pub fn is_bilingual(&self) -> bool {
+        self.timed_lines
+            .windows(2)
+            .any(|lines| lines[0].time == lines[1].time)
+    }
+    /// Interleaves `translation` into these lyrics: each translated line
+    /// follows the original lines with the same timestamp. Header tags are
+    /// taken from the original. A translated line at a time with no original
+    /// line stands alone, so [`Lyrics::split`] takes it for an original.
+    pub fn merge(&self, translation: &Lyrics) -> Lyrics {
+        let mut timed_lines = self.timed_lines.clone();
+        timed_lines.extend_from_slice(&translation.timed_lines);
+        // The sort is stable, so originals stay in front.
+        Lyrics::from_parts(self.tags.clone(), timed_lines)
+    }
+    /// Splits merged lyrics into the originals, the first line of every
+    /// timestamp, and the translations, every other line. Both keep the
+    /// header tags.
+    pub fn split(&self) -> (Lyrics, Lyrics) {
+        let mut original = Vec::new();
+        let mut translation: Vec<LyricsLine> = Vec::new();
+        for lines in self.timed_lines.chunk_by(|a, b| a.time == b.time) {
+            original.push(lines[0].clone());
+            translation.extend_from_slice(&lines[1..]);
+        }
+        (
+            Lyrics::from_parts(self.tags.clone(), original),
+            Lyrics::from_parts(self.tags.clone(), translation),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lyrics: &Lyrics) -> Vec<(u64, &str)> {
+        lyrics
+            .timed_lines()
+            .iter()
+            .map(|line| (line.time.as_millis(), line.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_merge() {
+        let original = Lyrics::from("[ti:Song]\n[00:03.00]three\n[00:01.00]one\n[00:02.00]two");
+        let translation = Lyrics::from("[00:02.00]deux\n[00:01.00]un\n[00:03.00]trois");
+        assert!(!original.is_bilingual());
+
+        let merged = original.merge(&translation);
+        assert!(merged.is_bilingual());
+        assert_eq!(merged.tags(), original.tags());
+        // Originals stay in front of translations with the same time, even
+        // though both inputs were out of order.
+        assert_eq!(
+            merged.groups().collect::<Vec<_>>(),
+            [
+                LineGroup {
+                    time: LyricsDuration::from_millis(1000),
+                    original: "one",
+                    translations: vec!["un"],
+                },
+                LineGroup {
+                    time: LyricsDuration::from_millis(2000),
+                    original: "two",
+                    translations: vec!["deux"],
+                },
+                LineGroup {
+                    time: LyricsDuration::from_millis(3000),
+                    original: "three",
+                    translations: vec!["trois"],
+                },
+            ]
+        );
+
+        let (split_original, split_translation) = merged.split();
+        assert_eq!(lines(&split_original), lines(&original));
+        assert_eq!(lines(&split_translation), lines(&translation));
+        assert_eq!(split_original.tags(), original.tags());
+    }
+
+    #[test]
+    fn keeps_unmatched_translations() {
+        let original = Lyrics::from("[00:01.00]one\n[00:03.00]three");
+        let translation = Lyrics::from("[00:01.00]un\n[00:02.00]deux");
+        let merged = original.merge(&translation);
+        assert_eq!(
+            lines(&merged),
+            [(1000, "one"), (1000, "un"), (2000, "deux"), (3000, "three")]
+        );
+        let groups: Vec<_> = merged.groups().collect();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(
+            (groups[1].original, groups[1].translations.len()),
+            ("deux", 0)
+        );
+
+        // The unmatched line cannot be told apart from an original.
+        let (split_original, split_translation) = merged.split();
+        assert_eq!(
+            lines(&split_original),
+            [(1000, "one"), (2000, "deux"), (3000, "three")]
+        );
+        assert_eq!(lines(&split_translation), [(1000, "un")]);
+    }
+}
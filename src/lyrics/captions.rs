@@ -0,0 +1,699 @@
+//! SubRip, WebVTT and TTML captions.
+//!
+//! A cue lasts until the next line starts; the last one until the end of the
+//! track when it is known, or for 5 seconds otherwise. Lines sharing a
+//! timestamp become one cue with several lines of text.
+
+use std::{borrow::Cow, fmt::Write, time::Duration};
+
+use scraper::{ElementRef, Html};
+
+use super::{Lyrics, LyricsDuration, LyricsLine, RichLyrics};
+use crate::{Error, Result};
+
+const LAST_CUE: LyricsDuration = LyricsDuration::from_millis(5000);
+
+struct Cue {
+    start: LyricsDuration,
+    end: LyricsDuration,
+    lines: Vec<String>,
+    /// Word timings of the first line, for karaoke.
+    words: Vec<(LyricsDuration, String)>,
+}
+
+fn cue_end(
+    start: LyricsDuration,
+    next: Option<LyricsDuration>,
+    track_end: Option<LyricsDuration>,
+) -> LyricsDuration {
+    next.or(track_end)
+        .filter(|end| *end > start)
+        .unwrap_or(start + LAST_CUE)
+}
+
+fn lyrics_cues(lyrics: &Lyrics, end: Option<Duration>) -> Vec<Cue> {
+    let track_end = end.map(LyricsDuration::from).or(lyrics.length());
+    let groups: Vec<_> = lyrics
+        .timed_lines()
+        .chunk_by(|a, b| a.time == b.time)
+        .collect();
+    let mut cues = Vec::new();
+    for (i, group) in groups.iter().enumerate() {
+        let lines: Vec<String> = group
+            .iter()
+            .map(|line| line.text.clone())
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        // Empty lines only end the previous cue.
+        if lines.is_empty() {
+            continue;
+        }
+        let start = group[0].time;
+        let next = groups.get(i + 1).map(|group| group[0].time);
+        cues.push(Cue {
+            start,
+            end: cue_end(start, next, track_end),
+            lines,
+            words: group[0]
+                .words
+                .iter()
+                .map(|word| (word.time, word.word.clone()))
+                .collect(),
+        });
+    }
+    cues
+}
+
+fn rich_lyrics_cues(lyrics: &RichLyrics, end: Option<Duration>) -> Vec<Cue> {
+    let track_end = end.map(LyricsDuration::from);
+    let lines: Vec<_> = lyrics
+        .lines()
+        .filter_map(|line| Some((line.start()?, line)))
+        .collect();
+    let mut cues = Vec::new();
+    for (i, (start, line)) in lines.iter().enumerate() {
+        let next = lines.get(i + 1).map(|(start, _)| *start);
+        cues.push(Cue {
+            start: *start,
+            end: line
+                .end
+                .filter(|end| end > start)
+                .unwrap_or_else(|| cue_end(*start, next, track_end)),
+            lines: vec![line.text()],
+            words: line
+                .items
+                .iter()
+                .map(|item| (item.time(), item.text().to_owned()))
+                .collect(),
+        });
+    }
+    cues
+}
+
+fn clock(time: LyricsDuration, separator: char) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{:0>2}:{:0>2}:{:0>2}{}{:0>3}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn write_srt(cues: &[Cue]) -> String {
+    let mut srt = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        let _ = writeln!(srt, "{}", i + 1);
+        let _ = writeln!(srt, "{} --> {}", clock(cue.start, ','), clock(cue.end, ','));
+        for line in &cue.lines {
+            let _ = writeln!(srt, "{}", line);
+        }
+        srt.push('\n');
+    }
+    srt
+}
+
+/// The first line with a `<timestamp>` before every word after the first
+/// one, and each word in a `<c>` span.
+fn karaoke(cue: &Cue) -> String {
+    let mut text = String::new();
+    for (time, word) in &cue.words {
+        if word.trim().is_empty() {
+            text.push_str(word);
+            continue;
+        }
+        if *time > cue.start && *time < cue.end {
+            let _ = write!(text, "<{}>", clock(*time, '.'));
+        }
+        let _ = write!(text, "<c>{}</c>", escape(word));
+    }
+    text
+}
+
+fn write_webvtt(cues: &[Cue]) -> String {
+    let mut vtt = String::from("WEBVTT\n\n");
+    for cue in cues {
+        let _ = writeln!(vtt, "{} --> {}", clock(cue.start, '.'), clock(cue.end, '.'));
+        for (i, line) in cue.lines.iter().enumerate() {
+            if i == 0 && !cue.words.is_empty() {
+                let _ = writeln!(vtt, "{}", karaoke(cue));
+            } else {
+                let _ = writeln!(vtt, "{}", escape(line));
+            }
+        }
+        vtt.push('\n');
+    }
+    vtt
+}
+
+fn write_ttml(cues: &[Cue]) -> String {
+    let mut ttml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<tt xmlns=\"http://www.w3.org/ns/ttml\">\n",
+        "  <body>\n",
+        "    <div>\n"
+    ));
+    for cue in cues {
+        let _ = write!(
+            ttml,
+            "      <p begin=\"{}\" end=\"{}\">",
+            clock(cue.start, '.'),
+            clock(cue.end, '.')
+        );
+        for (i, line) in cue.lines.iter().enumerate() {
+            if i > 0 {
+                ttml.push_str("<br/>");
+            }
+            if i > 0 || cue.words.is_empty() {
+                ttml.push_str(&escape(line));
+                continue;
+            }
+            // Span times are relative to the paragraph.
+            for (j, (time, word)) in cue.words.iter().enumerate() {
+                let end = cue.words.get(j + 1).map_or(cue.end, |(time, _)| *time);
+                let _ = write!(
+                    ttml,
+                    "<span begin=\"{}\" end=\"{}\">{}</span>",
+                    clock(*time - cue.start, '.'),
+                    clock(end - cue.start, '.'),
+                    escape(word)
+                );
+            }
+        }
+        ttml.push_str("</p>\n");
+    }
+    ttml.push_str("    </div>\n  </body>\n</tt>\n");
+    ttml
+}
+
+fn time_error(time: &str) -> Error {
+    Error::FmtError(format!("Invalid time: {}", time))
+}
+
+/// Parses `hh:mm:ss.fff`, `mm:ss.fff` or `hh:mm:ss,fff`. `None` when the
+/// time is too large to represent.
+fn parse_clock(time: &str) -> Result<Option<LyricsDuration>> {
+    let time = time.trim();
+    let (clock, fraction) = match time.rfind(['.', ',']) {
+        Some(i) => (&time[..i], &time[i + 1..]),
+        None => (time, ""),
+    };
+    let mut millis = 0u64;
+    let parts: Vec<_> = clock.split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return Err(time_error(time));
+    }
+    for part in parts {
+        let value: u64 = part.parse().map_err(|_| time_error(time))?;
+        let Some(sum) = millis.checked_mul(60).and_then(|m| m.checked_add(value)) else {
+            return Ok(None);
+        };
+        millis = sum;
+    }
+    let Some(mut millis) = millis.checked_mul(1000) else {
+        return Ok(None);
+    };
+    if !fraction.is_empty() {
+        if fraction.len() > 3 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(time_error(time));
+        }
+        let fraction = fraction.parse::<u64>().unwrap() * 10u64.pow(3 - fraction.len() as u32);
+        let Some(sum) = millis.checked_add(fraction) else {
+            return Ok(None);
+        };
+        millis = sum;
+    }
+    Ok(Some(LyricsDuration::from_millis(millis)))
+}
+
+/// Frame and tick rates of a TTML document, from the `ttp:` parameters of
+/// its `<tt>` element.
+struct TtmlRates {
+    /// Frames per second.
+    frame: f64,
+    /// Ticks per second.
+    tick: f64,
+}
+
+impl TtmlRates {
+    fn from_document(html: &Html) -> TtmlRates {
+        let tt = ttml_elements(html, "tt").next();
+        // The HTML parser lowercases attribute names.
+        let attr = |name: &str| tt.and_then(|tt| tt.value().attr(name));
+        let number = |name: &str| {
+            attr(name)
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|value| value.is_finite() && *value > 0.0)
+        };
+        let multiplier = attr("ttp:frameratemultiplier")
+            .and_then(|value| {
+                let mut parts = value.split_whitespace().map(|n| n.parse::<f64>().ok());
+                Some(parts.next()?? / parts.next()??)
+            })
+            .filter(|value| value.is_finite() && *value > 0.0)
+            .unwrap_or(1.0);
+        let frame_rate = number("ttp:framerate");
+        let sub_frame_rate = number("ttp:subframerate").unwrap_or(1.0);
+        TtmlRates {
+            frame: frame_rate.unwrap_or(30.0) * multiplier,
+            tick: number("ttp:tickrate")
+                .or(frame_rate.map(|rate| rate * sub_frame_rate))
+                .unwrap_or(1.0),
+        }
+    }
+}
+
+/// Milliseconds as a duration, `None` when they do not fit.
+fn millis_f64(millis: f64) -> Option<LyricsDuration> {
+    (millis < u64::MAX as f64).then(|| LyricsDuration::from_millis(millis.round() as u64))
+}
+
+/// Parses a TTML clock time, `hh:mm:ss.fff` or `hh:mm:ss:frames`, or an
+/// offset such as `1.5s`, `1500ms`, `2m`, `1h`, `12f` or `9000t`. `None`
+/// when the time is too large to represent.
+fn parse_ttml_time(time: &str, rates: &TtmlRates) -> Result<Option<LyricsDuration>> {
+    let time = time.trim();
+    if time.matches(':').count() == 3 {
+        let (clock, frames) = time.rsplit_once(':').unwrap();
+        let frames: f64 = match frames.parse() {
+            Ok(frames) if frames >= 0.0 => frames,
+            _ => return Err(time_error(time)),
+        };
+        let Some(clock) = parse_clock(clock)? else {
+            return Ok(None);
+        };
+        return Ok(millis_f64(
+            clock.as_millis() as f64 + frames / rates.frame * 1000.0,
+        ));
+    }
+    if time.contains(':') {
+        return parse_clock(time);
+    }
+    let (value, unit) = time.split_at(
+        time.find(|c: char| c.is_ascii_alphabetic())
+            .unwrap_or(time.len()),
+    );
+    let value: f64 = match value.parse() {
+        Ok(value) if value >= 0.0 => value,
+        _ => return Err(time_error(time)),
+    };
+    let scale = match unit {
+        "h" => 3_600_000.0,
+        "m" => 60_000.0,
+        "s" => 1000.0,
+        "ms" => 1.0,
+        "f" => 1000.0 / rates.frame,
+        "t" => 1000.0 / rates.tick,
+        _ => return Err(time_error(time)),
+    };
+    Ok(millis_f64(value * scale))
+}
+
+/// `name` without its namespace prefix, such as `p` for `tt:p`.
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// The elements named `name` in document order, ignoring namespace
+/// prefixes, as EBU-TT-D files write `<tt:p>`.
+fn ttml_elements<'a>(html: &'a Html, name: &'a str) -> impl Iterator<Item = ElementRef<'a>> {
+    html.root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(move |element| local_name(element.value().name()) == name)
+}
+
+/// The byte range of the first start or end tag named `name`, ignoring
+/// namespace prefixes: `head` also finds `<tt:head>`.
+fn find_tag(text: &str, name: &str, end_tag: bool) -> Option<(usize, usize)> {
+    let mut from = 0;
+    loop {
+        let start = from + text[from..].find('<')?;
+        let end = start + text[start..].find('>')? + 1;
+        let tag = &text[start + 1..end - 1];
+        let (is_end_tag, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag),
+            None => (false, tag),
+        };
+        let tag_name = tag
+            .split(|c: char| c.is_ascii_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        if is_end_tag == end_tag && local_name(tag_name) == name {
+            return Some((start, end));
+        }
+        from = start + 1;
+    }
+}
+
+/// Removes the `<head>` element. The HTML parser reads its empty elements,
+/// such as `<style/>` and `<region/>`, as start tags, and everything after
+/// a `<style>` as its text.
+fn strip_ttml_head(ttml: &str) -> Cow<'_, str> {
+    let Some((start, open_end)) = find_tag(ttml, "head", false) else {
+        return ttml.into();
+    };
+    let end = if ttml[..open_end].ends_with("/>") {
+        open_end
+    } else {
+        find_tag(&ttml[open_end..], "head", true).map_or(ttml.len(), |(_, end)| open_end + end)
+    };
+    format!("{}{}", &ttml[..start], &ttml[end..]).into()
+}
+
+/// Removes WebVTT markup such as `<c>`, `<v Name>` and timestamps.
+fn strip_tags(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => plain.push(c),
+            _ => (),
+        }
+    }
+    plain
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&amp;", "&")
+}
+
+/// Turns cues into timed lines. A cue ending before the next one starts is
+/// followed by an empty line, so the gap survives.
+fn cues_to_lyrics(mut cues: Vec<Cue>) -> Lyrics {
+    cues.sort_by_key(|cue| cue.start);
+    let mut timed_lines = Vec::new();
+    for (i, cue) in cues.iter().enumerate() {
+        for line in &cue.lines {
+            timed_lines.push(LyricsLine {
+                time: cue.start,
+                text: line.clone(),
+                words: Vec::new(),
+            });
+        }
+        if cues.get(i + 1).is_none_or(|next| next.start > cue.end) {
+            timed_lines.push(LyricsLine {
+                time: cue.end,
+                text: String::new(),
+                words: Vec::new(),
+            });
+        }
+    }
+    Lyrics::from_parts(Vec::new(), timed_lines)
+}
+
+/// Reads the cues of SubRip and WebVTT text.
+fn parse_text_cues(text: &str) -> Result<Vec<Cue>> {
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut cues = Vec::new();
+    for block in text.split("\n\n") {
+        let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+        let Some(timing) = lines.next() else {
+            // The header, NOTE, STYLE and REGION blocks
+            continue;
+        };
+        let (start, end) = timing.split_once("-->").unwrap();
+        // WebVTT cue settings follow the end time.
+        let end = end.split_whitespace().next().unwrap_or_default();
+        let (Some(start), Some(end)) = (parse_clock(start)?, parse_clock(end)?) else {
+            continue;
+        };
+        cues.push(Cue {
+            start,
+            end,
+            lines: lines
+                .map(strip_tags)
+                .filter(|line| !line.trim().is_empty())
+                .collect(),
+            words: Vec::new(),
+        });
+    }
+    Ok(cues)
+}
+
+fn ttml_text(element: ElementRef) -> String {
+    let mut text = String::new();
+    for node in element.descendants() {
+        if let Some(t) = node.value().as_text() {
+            text.push_str(t);
+        } else if node
+            .value()
+            .as_element()
+            .is_some_and(|e| local_name(e.name()) == "br")
+        {
+            text.push('\n');
+        }
+    }
+    text
+}
+
+impl Lyrics {
+    /// SubRip captions. `end` is the track duration.
+    pub fn to_srt(&self, end: Option<Duration>) -> String {
+        write_srt(&lyrics_cues(self, end))
+    }
+    /// WebVTT captions. `end` is the track duration.
+    pub fn to_webvtt(&self, end: Option<Duration>) -> String {
+        write_webvtt(&lyrics_cues(self, end))
+    }
+    /// TTML captions. `end` is the track duration.
+    pub fn to_ttml(&self, end: Option<Duration>) -> String {
+        write_ttml(&lyrics_cues(self, end))
+    }
+    pub fn from_srt(srt: &str) -> Result<Lyrics> {
+        Ok(cues_to_lyrics(parse_text_cues(srt)?))
+    }
+    pub fn from_webvtt(vtt: &str) -> Result<Lyrics> {
+        if !vtt.trim_start_matches('\u{feff}').starts_with("WEBVTT") {
+            return Err(Error::FmtError("Missing WEBVTT header".into()));
+        }
+        Ok(cues_to_lyrics(parse_text_cues(vtt)?))
+    }
+    /// Reads the timed `<p>` elements of TTML captions. Cues whose times
+    /// are too large to represent are skipped.
+    pub fn from_ttml(ttml: &str) -> Result<Lyrics> {
+        let html = Html::parse_fragment(&strip_ttml_head(ttml));
+        let rates = TtmlRates::from_document(&html);
+        let mut cues = Vec::new();
+        for p in ttml_elements(&html, "p") {
+            let Some(begin) = p.value().attr("begin") else {
+                continue;
+            };
+            let Some(start) = parse_ttml_time(begin, &rates)? else {
+                continue;
+            };
+            let end = match (p.value().attr("end"), p.value().attr("dur")) {
+                (Some(end), _) => parse_ttml_time(end, &rates)?,
+                (None, Some(dur)) => parse_ttml_time(dur, &rates)?.and_then(|dur| {
+                    start
+                        .as_millis()
+                        .checked_add(dur.as_millis())
+                        .map(LyricsDuration::from_millis)
+                }),
+                (None, None) => Some(start + LAST_CUE),
+            };
+            let Some(end) = end else {
+                continue;
+            };
+            cues.push(Cue {
+                start,
+                end,
+                lines: ttml_text(p)
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(String::from)
+                    .collect(),
+                words: Vec::new(),
+            });
+        }
+        Ok(cues_to_lyrics(cues))
+    }
+}
+
+impl RichLyrics {
+    /// SubRip captions. `end` is the track duration.
+    pub fn to_srt(&self, end: Option<Duration>) -> String {
+        write_srt(&rich_lyrics_cues(self, end))
+    }
+    /// WebVTT captions with the word timings as karaoke timestamps.
+    pub fn to_webvtt(&self, end: Option<Duration>) -> String {
+        write_webvtt(&rich_lyrics_cues(self, end))
+    }
+    /// TTML captions with a timed span per word.
+    pub fn to_ttml(&self, end: Option<Duration>) -> String {
+        write_ttml(&rich_lyrics_cues(self, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cues(lyrics: &Lyrics) -> Vec<(u64, &str)> {
+        lyrics
+            .timed_lines()
+            .iter()
+            .map(|line| (line.time.as_millis(), line.text.as_str()))
+            .collect()
+    }
+
+    const STYLED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tt xmlns="http://www.w3.org/ns/ttml" xmlns:tts="http://www.w3.org/ns/ttml#styling"
+    xmlns:ttp="http://www.w3.org/ns/ttml#parameter" ttp:tickRate="10000000">
+  <head>
+    <styling>
+      <style xml:id="s1" tts:color="white"/>
+    </styling>
+    <layout>
+      <region xml:id="bottom" tts:origin="10% 80%"/>
+    </layout>
+  </head>
+  <body region="bottom" style="s1">
+    <div>
+      <p begin="00:00:01.000" end="00:00:02.500">One<br/>two</p>
+      <p begin="30000000t" dur="10000000t"><span>Three</span> four</p>
+    </div>
+  </body>
+</tt>"#;
+
+    #[test]
+    fn reads_styled_ttml() {
+        let lyrics = Lyrics::from_ttml(STYLED).unwrap();
+        assert_eq!(
+            cues(&lyrics),
+            [
+                (1000, "One"),
+                (1000, "two"),
+                (2500, ""),
+                (3000, "Three four"),
+                (4000, "")
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_prefixed_ttml() {
+        let ttml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tt:tt xmlns:tt="http://www.w3.org/ns/ttml" xmlns:ttp="http://www.w3.org/ns/ttml#parameter"
+    ttp:timeBase="media" ttp:frameRate="25" xml:lang="en">
+  <tt:head>
+    <tt:styling><tt:style xml:id="s1"/></tt:styling>
+  </tt:head>
+  <tt:body>
+    <tt:div>
+      <tt:p begin="00:00:01.000" end="00:00:02.000"><tt:span>One</tt:span><tt:br/>two</tt:p>
+      <tt:p begin="00:00:03:00" end="00:00:03:20"><tt:span style="s1">Three</tt:span></tt:p>
+    </tt:div>
+  </tt:body>
+</tt:tt>"#;
+        let lyrics = Lyrics::from_ttml(ttml).unwrap();
+        assert_eq!(
+            cues(&lyrics),
+            [
+                (1000, "One"),
+                (1000, "two"),
+                (2000, ""),
+                (3000, "Three"),
+                (3800, "")
+            ]
+        );
+    }
+
+    #[test]
+    fn strips_prefixed_head() {
+        let ttml = "<tt:tt><tt:head><style/></tt:head><p begin=\"1s\">a</p></tt:tt>";
+        assert_eq!(
+            strip_ttml_head(ttml),
+            "<tt:tt><p begin=\"1s\">a</p></tt:tt>"
+        );
+        assert_eq!(strip_ttml_head("<tt><head/><p/></tt>"), "<tt><p/></tt>");
+        assert_eq!(strip_ttml_head("<tt><header/></tt>"), "<tt><header/></tt>");
+    }
+
+    #[test]
+    fn parses_ttml_times() {
+        let rates = TtmlRates {
+            frame: 25.0,
+            tick: 1.0,
+        };
+        let millis = |time| {
+            parse_ttml_time(time, &rates)
+                .unwrap()
+                .map(|t| t.as_millis())
+        };
+        assert_eq!(millis("1.5s"), Some(1500));
+        assert_eq!(millis("1500ms"), Some(1500));
+        assert_eq!(millis("2m"), Some(120_000));
+        assert_eq!(millis("1h"), Some(3_600_000));
+        assert_eq!(millis("50f"), Some(2000));
+        assert_eq!(millis("3t"), Some(3000));
+        assert_eq!(millis("00:00:01:05"), Some(1200));
+        assert_eq!(millis("01:02.5"), Some(62_500));
+        assert_eq!(millis("100000000000000000000h"), None);
+        assert_eq!(millis("99999999999999999:00:00"), None);
+        for time in ["", "1", "1x", "-1s", "NaN s", "00:00:01:x"] {
+            assert!(parse_ttml_time(time, &rates).is_err(), "{}", time);
+        }
+    }
+
+    #[test]
+    fn reads_ttml_rates() {
+        let ttml = r#"<tt ttp:frameRate="30" ttp:frameRateMultiplier="1000 1001">
+            <body><p begin="30f" end="00:00:02:00">a</p></body></tt>"#;
+        let lyrics = Lyrics::from_ttml(ttml).unwrap();
+        assert_eq!(cues(&lyrics), [(1001, "a"), (2000, "")]);
+    }
+
+    #[test]
+    fn skips_overflowing_cues() {
+        let ttml = r#"<tt><body>
+            <p begin="1s" end="2s">kept</p>
+            <p begin="18446744073709551s" dur="18446744073709551s">sum</p>
+            <p begin="100000000000000000000h">begin</p>
+        </body></tt>"#;
+        let lyrics = Lyrics::from_ttml(ttml).unwrap();
+        assert_eq!(cues(&lyrics), [(1000, "kept"), (2000, "")]);
+        let srt = "1\n99999999999999999:00:00,000 --> 99999999999999999:00:01,000\nx\n\n\
+                   2\n00:00:01,000 --> 00:00:02,000\ny\n";
+        assert_eq!(
+            cues(&Lyrics::from_srt(srt).unwrap()),
+            [(1000, "y"), (2000, "")]
+        );
+    }
+
+    #[test]
+    fn round_trips_captions() {
+        let lyrics = Lyrics::from("[00:01.00]one\n[00:02.50]two & three\n[00:04.00]");
+        let expected = [(1000, "one"), (2500, "two & three"), (4000, "")];
+        assert_eq!(
+            cues(&Lyrics::from_srt(&lyrics.to_srt(None)).unwrap()),
+            expected
+        );
+        assert_eq!(
+            cues(&Lyrics::from_webvtt(&lyrics.to_webvtt(None)).unwrap()),
+            expected
+        );
+        assert_eq!(
+            cues(&Lyrics::from_ttml(&lyrics.to_ttml(None)).unwrap()),
+            expected
+        );
+        // SubRip has no escapes for markup.
+        let lyrics = Lyrics::from("[00:01.00]<b>");
+        let vtt = Lyrics::from_webvtt(&lyrics.to_webvtt(None)).unwrap();
+        assert_eq!(cues(&vtt)[0], (1000, "<b>"));
+        let ttml = Lyrics::from_ttml(&lyrics.to_ttml(None)).unwrap();
+        assert_eq!(cues(&ttml)[0], (1000, "<b>"));
+        assert!(Lyrics::from_webvtt("00:01.000 --> 00:02.000\na").is_err());
+    }
+}
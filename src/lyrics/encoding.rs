@@ -0,0 +1,71 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+
+use super::Lyrics;
+
+/// Decodes text of unknown encoding: a BOM wins, then UTF-8 if the bytes are
+/// valid UTF-8, then the guess of a detector covering legacy encodings such
+/// as GBK, Big5 and Shift-JIS. Undecodable bytes become U+FFFD.
+pub(crate) fn decode(bytes: &[u8]) -> (String, &'static Encoding) {
+    let (encoding, bytes) = match Encoding::for_bom(bytes) {
+        Some((encoding, bom)) => (encoding, &bytes[bom..]),
+        None if std::str::from_utf8(bytes).is_ok() => (UTF_8, bytes),
+        None => {
+            let mut detector = EncodingDetector::new();
+            detector.feed(bytes, true);
+            (detector.guess(None, false), bytes)
+        }
+    };
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    (text.into_owned(), encoding)
+}
+
+impl Lyrics {
+    /// Reads lyrics from a file's bytes, detecting the encoding. Returns the
+    /// encoding that was used along with them.
+    pub fn from_bytes(bytes: &[u8]) -> (Lyrics, &'static Encoding) {
+        let (text, encoding) = decode(bytes);
+        (Lyrics::from(text), encoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{BIG5, GBK, SHIFT_JIS, UTF_16LE};
+
+    const CHINESE: &str =
+        "[00:01.00]我们一起唱这首歌，直到天亮也不停止。\n[00:05.00]月亮代表我的心";
+
+    #[test]
+    fn honours_boms() {
+        let mut utf8 = b"\xEF\xBB\xBF".to_vec();
+        utf8.extend_from_slice("[00:01.00]é".as_bytes());
+        assert_eq!(decode(&utf8), ("[00:01.00]é".into(), UTF_8));
+        let mut utf16 = b"\xFF\xFE".to_vec();
+        utf16.extend("[ti:é]".encode_utf16().flat_map(u16::to_le_bytes));
+        let (lyrics, encoding) = Lyrics::from_bytes(&utf16);
+        assert_eq!((lyrics.title(), encoding), (Some("é"), UTF_16LE));
+    }
+
+    #[test]
+    fn prefers_utf8() {
+        assert_eq!(decode(CHINESE.as_bytes()), (CHINESE.into(), UTF_8));
+        assert_eq!(decode(b""), (String::new(), UTF_8));
+    }
+
+    #[test]
+    fn detects_legacy_encodings() {
+        let (bytes, _, _) = GBK.encode(CHINESE);
+        assert_eq!(decode(&bytes).0, CHINESE);
+        let traditional =
+            "[00:01.00]我們一起唱這首歌，直到天亮也不停止。\n[00:05.00]月亮代表我的心";
+        let (bytes, _, _) = BIG5.encode(traditional);
+        assert_eq!(decode(&bytes), (traditional.into(), BIG5));
+        let japanese = "[00:01.00]東京の夜空に星がきらきらと輝いています。\n[00:04.00]ありがとう";
+        let (bytes, _, _) = SHIFT_JIS.encode(japanese);
+        assert_eq!(decode(&bytes), (japanese.into(), SHIFT_JIS));
+        let (lyrics, _) = Lyrics::from_bytes(&bytes);
+        assert_eq!(lyrics.timed_lines()[1].text, "ありがとう");
+    }
+}
@@ -0,0 +1,241 @@
+use super::{LyricsDuration, Word};
+
+/// One line of an LRC file.
+pub(crate) enum LrcLine<'a> {
+    /// `[key:value]`
+    Tag(&'a str, &'a str),
+    /// `[mm:ss.xx][mm:ss.xx]text`
+    Timed(Vec<LyricsDuration>, &'a str),
+    /// A line without any timestamp, including empty lines.
+    Text,
+}
+
+/// Parses the inside of a timestamp: `m:ss`, `mm:ss.xx`, `mm:ss.xxx` or
+/// `mm:ss:xx`, with any number of minute digits.
+pub(crate) fn parse_timestamp(time: &str) -> Option<LyricsDuration> {
+    let (min, rest) = time.trim().split_once(':')?;
+    let (secs, fraction) = match rest.find(['.', ':']) {
+        Some(i) => (&rest[..i], &rest[i + 1..]),
+        None => (rest, ""),
+    };
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if min.is_empty()
+        || !all_digits(min)
+        || secs.is_empty()
+        || secs.len() > 2
+        || !all_digits(secs)
+        || fraction.len() > 3
+        || !all_digits(fraction)
+    {
+        return None;
+    }
+    let secs: u64 = secs.parse().ok()?;
+    if secs >= 60 {
+        return None;
+    }
+    let millis = match fraction.len() {
+        0 => 0,
+        len => fraction.parse::<u64>().ok()? * 10u64.pow(3 - len as u32),
+    };
+    let min: u64 = min.parse().ok()?;
+    let millis = min.checked_mul(60_000)?.checked_add(secs * 1000 + millis)?;
+    Some(LyricsDuration::from_millis(millis))
+}
+
+/// Splits a `[key:value]` header tag. Keys are made of ASCII letters, digits
+/// and `#`.
+pub(crate) fn parse_tag(content: &str) -> Option<(&str, &str)> {
+    let (key, value) = content.split_once(':')?;
+    let key = key.trim();
+    if key.is_empty()
+        || !key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'#')
+        || key.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    Some((key, value.trim()))
+}
+
+pub(crate) fn parse_line(line: &str) -> LrcLine<'_> {
+    let mut times = Vec::new();
+    let mut rest = line;
+    while let Some(content) = rest.strip_prefix('[') {
+        let Some(end) = content.find(']') else {
+            break;
+        };
+        match parse_timestamp(&content[..end]) {
+            Some(time) => {
+                times.push(time);
+                rest = &content[end + 1..];
+            }
+            None if times.is_empty() && content[end + 1..].trim().is_empty() => {
+                if let Some((key, value)) = parse_tag(&content[..end]) {
+                    return LrcLine::Tag(key, value);
+                }
+                break;
+            }
+            None => break,
+        }
+    }
+    if times.is_empty() {
+        LrcLine::Text
+    } else {
+        LrcLine::Timed(times, rest.trim())
+    }
+}
+
+/// Splits enhanced LRC text, `<00:12.00>Hello <00:12.50>world`, into the
+/// plain text and its words. Text before the first word timestamp starts at
+/// `start`. Without word timestamps there are no words.
+pub(crate) fn parse_words(text: &str, start: LyricsDuration) -> (String, Vec<Word>) {
+    let mut plain = String::new();
+    let mut words: Vec<Word> = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('<') {
+        let time = rest[open + 1..]
+            .find('>')
+            .and_then(|close| Some((parse_timestamp(&rest[open + 1..open + 1 + close])?, close)));
+        let Some((time, close)) = time else {
+            plain.push_str(&rest[..=open]);
+            if let Some(word) = words.last_mut() {
+                word.word.push_str(&rest[..=open]);
+            }
+            rest = &rest[open + 1..];
+            continue;
+        };
+        let before = &rest[..open];
+        plain.push_str(before);
+        match words.last_mut() {
+            Some(word) => word.word.push_str(before),
+            None if !before.is_empty() => words.push(Word {
+                time: start,
+                word: before.into(),
+            }),
+            None => (),
+        }
+        words.push(Word {
+            time,
+            word: String::new(),
+        });
+        rest = &rest[open + close + 2..];
+    }
+    plain.push_str(rest);
+    match words.last_mut() {
+        Some(word) => word.word.push_str(rest),
+        None => return (plain, Vec::new()),
+    }
+    (plain.trim().to_owned(), words)
+}
+
+/// How many digits of a second an LRC timestamp is written with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LrcPrecision {
+    /// `[mm:ss.xx]`, understood by every player.
+    #[default]
+    Centiseconds,
+    /// `[mm:ss.xxx]`
+    Milliseconds,
+}
+
+pub(crate) fn format_timestamp(time: LyricsDuration, precision: LrcPrecision) -> String {
+    let millis = time.as_millis();
+    let (min, secs) = (millis / 60_000, millis / 1000 % 60);
+    match precision {
+        LrcPrecision::Centiseconds => {
+            format!("{:0>2}:{:0>2}.{:0>2}", min, secs, millis % 1000 / 10)
+        }
+        LrcPrecision::Milliseconds => format!("{:0>2}:{:0>2}.{:0>3}", min, secs, millis % 1000),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(time: &str) -> Option<u64> {
+        parse_timestamp(time).map(|time| time.as_millis())
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(millis("00:12.34"), Some(12_340));
+        assert_eq!(millis("00:12.345"), Some(12_345));
+        assert_eq!(millis("00:12.5"), Some(12_500));
+        assert_eq!(millis("01:02:03"), Some(62_030));
+        assert_eq!(millis("1:02"), Some(62_000));
+        assert_eq!(millis("100:00.00"), Some(6_000_000));
+        assert_eq!(millis(" 00:01 "), Some(1_000));
+    }
+
+    #[test]
+    fn rejects_bad_timestamps() {
+        for time in [
+            "",
+            ":12",
+            "00:",
+            "00:60",
+            "00:123",
+            "00:12.3456",
+            "a:12",
+            "-1:00",
+        ] {
+            assert_eq!(millis(time), None, "{}", time);
+        }
+        assert_eq!(millis("18446744073709551615:00"), None);
+        assert_eq!(millis("307445734561825860:00"), None);
+        assert!(matches!(
+            parse_line("[307445734561825860:00]text"),
+            LrcLine::Text
+        ));
+    }
+
+    #[test]
+    fn parses_lines() {
+        assert!(matches!(
+            parse_line("[ar: Someone ]"),
+            LrcLine::Tag("ar", "Someone")
+        ));
+        assert!(matches!(
+            parse_line("[00:01.00][ar:x]"),
+            LrcLine::Timed(_, "[ar:x]")
+        ));
+        assert!(matches!(parse_line("[12:34]"), LrcLine::Timed(_, "")));
+        assert!(matches!(parse_line("[ar:x] text"), LrcLine::Text));
+        assert!(matches!(parse_line("plain"), LrcLine::Text));
+        let LrcLine::Timed(times, text) = parse_line("[00:12.00][01:30.00] chorus ") else {
+            panic!("not timed");
+        };
+        let times: Vec<_> = times.iter().map(LyricsDuration::as_millis).collect();
+        assert_eq!((times, text), (vec![12_000, 90_000], "chorus"));
+    }
+
+    fn split_words(text: &str, start: u64) -> (String, Vec<(u64, String)>) {
+        let (plain, words) = parse_words(text, LyricsDuration::from_millis(start));
+        let words = words
+            .into_iter()
+            .map(|word| (word.time.as_millis(), word.word))
+            .collect();
+        (plain, words)
+    }
+
+    #[test]
+    fn parses_words() {
+        let (plain, words) = split_words("<00:12.00>Hello <00:12.50>world", 12_000);
+        assert_eq!(plain, "Hello world");
+        assert_eq!(words, [(12_000, "Hello ".into()), (12_500, "world".into())]);
+        let (plain, words) = split_words("Oh <00:13.00>yeah", 12_000);
+        assert_eq!(plain, "Oh yeah");
+        assert_eq!(words, [(12_000, "Oh ".into()), (13_000, "yeah".into())]);
+    }
+
+    #[test]
+    fn keeps_text_without_words() {
+        assert_eq!(
+            split_words("plain text", 0),
+            ("plain text".into(), Vec::new())
+        );
+        let (plain, words) = split_words("<00:01.00>a <b> <c", 0);
+        assert_eq!(plain, "a <b> <c");
+        assert_eq!(words, [(1000, "a <b> <c".into())]);
+    }
+}
@@ -1,128 +1,547 @@
-#[derive(Debug, Default)]
+mod bilingual;
+mod captions;
+mod encoding;
+mod lrc;
+mod rlrc;
+mod sylt;
+mod sync;
+mod validate;
+
+pub use bilingual::LineGroup;
+pub(crate) use encoding::decode;
+use lrc::LrcLine;
+pub use lrc::LrcPrecision;
+use std::{
+    ops::{Add, AddAssign, Sub, SubAssign},
+    time::Duration,
+};
+pub use sync::SyncedLyrics;
+pub use validate::{Diagnostic, DiagnosticKind};
+
+#[derive(Debug, Default, Clone)]
 pub struct Lyrics {
     lines: Vec<String>,
+    tags: Vec<(String, String)>,
+    timed_lines: Vec<LyricsLine>,
+}
+
+/// A line of lyrics and the time it starts at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LyricsLine {
+    pub time: LyricsDuration,
+    /// The text without word timestamps.
+    pub text: String,
+    /// Word timestamps of enhanced LRC, `<00:12.50>word`. Joined, their texts
+    /// give `text` up to surrounding whitespace.
+    pub words: Vec<Word>,
 }
 
 impl<T: AsRef<str>> From<T> for Lyrics {
     fn from(value: T) -> Self {
-        Self {
-            lines: split_lyrics(value.as_ref())
-                .into_iter()
-                .map(|s| s.to_owned())
-                .collect(),
+        let lines = split_lyrics(value.as_ref());
+        let mut tags = Vec::new();
+        let mut timed_lines = Vec::new();
+        for line in &lines {
+            match lrc::parse_line(line) {
+                LrcLine::Tag(key, value) => tags.push((key.to_ascii_lowercase(), value.to_owned())),
+                LrcLine::Timed(times, text) => {
+                    let first = times[0];
+                    let (text, words) = lrc::parse_words(text, first);
+                    for time in times {
+                        // Word times are given for the first timestamp.
+                        let shift = time.as_millis() as i64 - first.as_millis() as i64;
+                        timed_lines.push(LyricsLine {
+                            time,
+                            text: text.clone(),
+                            words: words
+                                .iter()
+                                .map(|word| Word {
+                                    time: word.time.shift(shift),
+                                    word: word.word.clone(),
+                                })
+                                .collect(),
+                        })
+                    }
+                }
+                LrcLine::Text => (),
+            }
         }
+        let mut lyrics = Self {
+            lines,
+            tags,
+            timed_lines,
+        };
+        // A positive offset makes the lyrics appear sooner.
+        lyrics.shift(lyrics.offset().saturating_neg());
+        // Stable, so lines sharing a timestamp keep their order in the file.
+        lyrics.timed_lines.sort_by_key(|line| line.time);
+        lyrics
     }
 }
 
+/// Splits on `\r\n`, `\n` and lone `\r` line endings.
 fn split_lyrics(lyrics: &str) -> Vec<String> {
-    let mut pre_pos = 0;
-    let mut pos = 0;
-    let bytes = lyrics.as_bytes();
-    let mut lyc = Vec::new();
-    while pos < bytes.len() {
-        let b = bytes[pos];
-        if b == b'\r' || b == b'\n' {
-            let str = lyrics[pre_pos..pos].trim().to_string();
-            lyc.push(str);
-            if pos + 1 < bytes.len() && (bytes[pos + 1] == b'\n' || bytes[pos + 1] == b'\r') {
-                pre_pos = pos + 2;
-            } else {
-                pre_pos = pos + 1;
-            }
-            pos += 1;
-        }
-        pos += 1;
+    let lyrics = lyrics.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut lines: Vec<String> = lyrics
+        .split(['\n', '\r'])
+        .map(|s| s.trim().to_owned())
+        .collect();
+    // Like `str::lines`, a final line ending does not start another line.
+    if lyrics.is_empty() || lyrics.ends_with(['\n', '\r']) {
+        lines.pop();
     }
-    lyc
+    lines
 }
 
 impl Lyrics {
+    /// Builds lyrics from timed lines, with `lines()` made of the LRC text
+    /// they would be written as.
+    pub(crate) fn from_parts(
+        tags: Vec<(String, String)>,
+        mut timed_lines: Vec<LyricsLine>,
+    ) -> Lyrics {
+        timed_lines.sort_by_key(|line| line.time);
+        let mut lyrics = Lyrics {
+            lines: Vec::new(),
+            tags,
+            timed_lines,
+        };
+        lyrics.lines = split_lyrics(&lyrics.to_lrc(LrcPrecision::Milliseconds));
+        lyrics
+    }
     pub fn lines(&self) -> &Vec<String> {
         &self.lines
     }
     pub fn lines_with_time(&self) -> impl Iterator<Item = (Option<LyricsDuration>, &str)> {
         self.lines.iter().map(|s| get_duration(s))
     }
+    /// Timed lines sorted by time, with `[offset:]` applied. A line with
+    /// several timestamps appears once per timestamp.
+    pub fn timed_lines(&self) -> &[LyricsLine] {
+        &self.timed_lines
+    }
+    /// Looks up timed lines by playback position.
+    pub fn synced(&self) -> SyncedLyrics<'_> {
+        SyncedLyrics::new(&self.timed_lines, self.length().map(Duration::from))
+    }
+    /// Moves every timed line by `millis`, later when positive. Times stop
+    /// at zero. `lines()` and `lines_with_time()` still return the text the
+    /// lyrics were read from; [`Lyrics::to_lrc`] writes the shifted times.
+    pub fn shift(&mut self, millis: i64) {
+        for line in &mut self.timed_lines {
+            line.time = line.time.shift(millis);
+            for word in &mut line.words {
+                word.time = word.time.shift(millis);
+            }
+        }
+    }
+    /// Header tags such as `[ar:]` in file order, with lowercase keys.
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+    pub fn artist(&self) -> Option<&str> {
+        self.tag("ar")
+    }
+    pub fn title(&self) -> Option<&str> {
+        self.tag("ti")
+    }
+    pub fn album(&self) -> Option<&str> {
+        self.tag("al")
+    }
+    /// The creator of the LRC file.
+    pub fn by(&self) -> Option<&str> {
+        self.tag("by")
+    }
+    /// The `[offset:]` tag in milliseconds.
+    pub fn offset(&self) -> i64 {
+        self.tag("offset")
+            .and_then(|offset| offset.trim_start_matches('+').parse().ok())
+            .unwrap_or(0)
+    }
+    pub fn length(&self) -> Option<LyricsDuration> {
+        self.tag("length").and_then(lrc::parse_timestamp)
+    }
+    /// Sets a header tag, replacing any existing tag with the same key.
+    pub fn set_tag(&mut self, key: &str, value: impl Into<String>) {
+        let key = key.to_ascii_lowercase();
+        let value = value.into();
+        match self.tags.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.tags.push((key, value)),
+        }
+    }
+    pub fn remove_tag(&mut self, key: &str) {
+        self.tags.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+    }
+    /// Keep the lines sorted by time when editing them. As with
+    /// [`Lyrics::shift`], `lines()` and `lines_with_time()` still return the
+    /// text the lyrics were read from.
+    pub fn timed_lines_mut(&mut self) -> &mut Vec<LyricsLine> {
+        &mut self.timed_lines
+    }
+    /// Writes the header tags followed by one line per timed line. The
+    /// `[offset:]` tag is added back to the timestamps, so reading the output
+    /// gives the same lyrics again.
+    pub fn to_lrc(&self, precision: LrcPrecision) -> String {
+        let mut lrc = String::new();
+        for (key, value) in &self.tags {
+            lrc.push_str(&format!("[{}:{}]\n", key, value));
+        }
+        let offset = self.offset();
+        for line in &self.timed_lines {
+            let time = lrc::format_timestamp(line.time.shift(offset), precision);
+            lrc.push_str(&format!("[{}]", time));
+            if line.words.is_empty() {
+                lrc.push_str(&line.text);
+            }
+            for word in &line.words {
+                let time = lrc::format_timestamp(word.time.shift(offset), precision);
+                lrc.push_str(&format!("<{}>{}", time, word.word));
+            }
+            lrc.push('\n');
+        }
+        lrc
+    }
 }
 
-#[derive(Debug, Default)]
+impl std::fmt::Display for Lyrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_lrc(LrcPrecision::default()))
+    }
+}
+
+/// A point in the lyrics, with millisecond precision.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct LyricsDuration {
-    min: u64,
-    secs: u64,
-    milliseconds: u64,
+    millis: u64,
 }
 impl std::fmt::Display for LyricsDuration {
+    /// `mm:ss.xx`, or `mm:ss.xxx` when the time is not a whole number of
+    /// centiseconds.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!(
-            "{:0>2}:{:0>2}.{:0>2}",
-            self.min, self.secs, self.milliseconds
-        ))
+        let millis = self.milliseconds();
+        if millis.is_multiple_of(10) {
+            write!(
+                f,
+                "{:0>2}:{:0>2}.{:0>2}",
+                self.minute(),
+                self.seconds(),
+                millis / 10
+            )
+        } else {
+            write!(
+                f,
+                "{:0>2}:{:0>2}.{:0>3}",
+                self.minute(),
+                self.seconds(),
+                millis
+            )
+        }
     }
 }
 
 impl LyricsDuration {
     pub fn from_min_secs_f64(min: u64, secs: f64) -> LyricsDuration {
-        let secs = (secs * 100.0) as u64;
-        Self {
-            min,
-            secs: secs / 100 % 60,
-            milliseconds: secs % 100,
-        }
+        Self::from_millis(min * 60_000 + (secs.max(0.0) * 1000.0).round() as u64)
+    }
+    pub const fn from_millis(millis: u64) -> LyricsDuration {
+        Self { millis }
+    }
+    pub fn as_millis(&self) -> u64 {
+        self.millis
     }
     pub fn minute(&self) -> u64 {
-        self.min
+        self.millis / 60_000
     }
     pub fn seconds(&self) -> u64 {
-        self.secs
+        self.millis / 1000 % 60
     }
     pub fn milliseconds(&self) -> u64 {
-        self.milliseconds
+        self.millis % 1000
+    }
+    /// Moves the time by `millis`, stopping at zero.
+    pub fn shift(self, millis: i64) -> LyricsDuration {
+        Self::from_millis(self.millis.saturating_add_signed(millis))
+    }
+}
+
+impl From<Duration> for LyricsDuration {
+    fn from(value: Duration) -> Self {
+        Self::from_millis(u64::try_from(value.as_millis()).unwrap_or(u64::MAX))
+    }
+}
+impl From<LyricsDuration> for Duration {
+    fn from(value: LyricsDuration) -> Self {
+        Duration::from_millis(value.millis)
     }
 }
 
-fn parse_time(time: &str) -> Option<LyricsDuration> {
-    let min: u64 = time[0..2].parse().ok()?;
-    let secs: f64 = time[3..].parse().ok()?;
-    Some(LyricsDuration::from_min_secs_f64(min, secs))
+/// Saturates instead of overflowing.
+impl Add for LyricsDuration {
+    type Output = LyricsDuration;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::from_millis(self.millis.saturating_add(rhs.millis))
+    }
 }
+/// Stops at zero.
+impl Sub for LyricsDuration {
+    type Output = LyricsDuration;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::from_millis(self.millis.saturating_sub(rhs.millis))
+    }
+}
+impl AddAssign for LyricsDuration {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+impl SubAssign for LyricsDuration {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
 fn get_duration(line: &str) -> (Option<LyricsDuration>, &str) {
-    if let Some(time) = line.get(0..10) {
-        if time.starts_with('[') && time.ends_with(']') {
-            return (parse_time(&time[1..9]), &line[10..]);
-        }
+    match lrc::parse_line(line) {
+        LrcLine::Timed(times, text) => (times.first().copied(), text),
+        _ => (None, line),
     }
-    (None, line)
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LyricsType {
     Lrc,
     Rlrc,
 }
-pub struct RichLyrics {}
 
-pub struct WordSpace {}
+impl LyricsType {
+    /// Rlrc is markup, so it is the only one of the two starting with `<`.
+    pub fn detect(lyrics: &str) -> LyricsType {
+        match lyrics
+            .trim_start_matches('\u{feff}')
+            .trim_start()
+            .starts_with('<')
+        {
+            true => LyricsType::Rlrc,
+            false => LyricsType::Lrc,
+        }
+    }
+}
+
+/// Lyrics in the Rlrc format, in document order.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RichLyrics {
+    pub tags: Vec<LyricTag>,
+}
+
+/// A space between two words, timed like a word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordSpace {
+    pub time: LyricsDuration,
+    pub space: String,
+}
+
+/// A word and the time it starts at.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Word {
-    time: String,
-    word: String,
+    pub time: LyricsDuration,
+    pub word: String,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LyricTag {
-    Wait,
-    P(),
+    /// A pause in the singing, from `start` until the next line.
+    Wait(LyricsDuration),
+    P(RichLyricsLine),
     Lyricist(Vec<String>),
     Composer(Vec<String>),
 }
 
-pub struct RichLyricsLine {}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineItem {
+    Word(Word),
+    Space(WordSpace),
+}
+
+impl LineItem {
+    pub fn time(&self) -> LyricsDuration {
+        match self {
+            LineItem::Word(word) => word.time,
+            LineItem::Space(space) => space.time,
+        }
+    }
+    pub fn text(&self) -> &str {
+        match self {
+            LineItem::Word(word) => &word.word,
+            LineItem::Space(space) => &space.space,
+        }
+    }
+}
+
+/// Which side of a duet a line is shown on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    #[default]
+    Left,
+    Right,
+}
 
-/*
-    <lyricist></lyricist> 写词人
-    <composer> 作曲家
-    <p name="" hor="left or right" end="00:00.00">
-        <word-space> or <word>
-           <duration>00:00.00</>
-           <w>Hello</w>
-           </>
-    </p>
-    <wait start="00:00.34" > // 自动生成, 3s
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RichLyricsLine {
+    /// The singer.
+    pub name: Option<String>,
+    pub hor: Alignment,
+    pub end: Option<LyricsDuration>,
+    pub items: Vec<LineItem>,
+}
 
-*/
+impl RichLyricsLine {
+    /// The time of the first word.
+    pub fn start(&self) -> Option<LyricsDuration> {
+        self.items.first().map(LineItem::time)
+    }
+    pub fn text(&self) -> String {
+        self.items.iter().map(LineItem::text).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(lyrics: &Lyrics) -> Vec<(u64, &str)> {
+        lyrics
+            .timed_lines()
+            .iter()
+            .map(|line| (line.time.as_millis(), line.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn splits_all_line_endings() {
+        assert_eq!(split_lyrics("a\r\nb\rc\nd\r"), ["a", "b", "c", "d"]);
+        assert_eq!(split_lyrics("\u{feff}a\n\nb\n"), ["a", "", "b"]);
+        assert!(split_lyrics("").is_empty());
+        let lyrics = Lyrics::from("[00:01.00]one\r[00:02.00]two\r");
+        assert_eq!(times(&lyrics), [(1000, "one"), (2000, "two")]);
+    }
+
+    #[test]
+    fn parses_lrc() {
+        let lyrics = Lyrics::from(
+            "[ti:Title]\n[AR:Artist]\n[length:03:20]\n\
+             [00:12.00][01:30.00]chorus\n[00:05.5]intro\nuntimed\n[00:12.00]second",
+        );
+        assert_eq!(lyrics.title(), Some("Title"));
+        assert_eq!(lyrics.artist(), Some("Artist"));
+        assert_eq!(lyrics.length(), Some(LyricsDuration::from_millis(200_000)));
+        assert_eq!(lyrics.lines().len(), 7);
+        assert_eq!(
+            times(&lyrics),
+            [
+                (5_500, "intro"),
+                (12_000, "chorus"),
+                (12_000, "second"),
+                (90_000, "chorus")
+            ]
+        );
+    }
+
+    #[test]
+    fn applies_offset() {
+        let lyrics = Lyrics::from("[offset:+500]\n[00:00.20]a\n[00:01.00]b");
+        assert_eq!(lyrics.offset(), 500);
+        assert_eq!(times(&lyrics), [(0, "a"), (500, "b")]);
+        let lyrics = Lyrics::from("[offset:-250]\n[00:01.00]b");
+        assert_eq!(times(&lyrics), [(1250, "b")]);
+    }
+
+    #[test]
+    fn writes_lrc() {
+        let input = "[ti:Title]\n[offset:100]\n[00:01.00]a\n[00:12.34]b\n[01:30.00]\n";
+        let lyrics = Lyrics::from(input);
+        assert_eq!(lyrics.to_lrc(LrcPrecision::Centiseconds), input);
+        assert_eq!(lyrics.to_string(), input);
+        let lyrics = Lyrics::from("[00:01.234]a\n[00:05]b");
+        assert_eq!(
+            lyrics.to_lrc(LrcPrecision::Milliseconds),
+            "[00:01.234]a\n[00:05.000]b\n"
+        );
+        assert_eq!(
+            lyrics.to_lrc(LrcPrecision::Centiseconds),
+            "[00:01.23]a\n[00:05.00]b\n"
+        );
+    }
+
+    #[test]
+    fn round_trips_edits() {
+        let mut lyrics = Lyrics::from("[ar:A]\n[00:12.00][00:02.00]x\n[100:00.00]y");
+        lyrics.shift(1500);
+        lyrics.set_tag("ti", "T");
+        let lrc = lyrics.to_lrc(LrcPrecision::Milliseconds);
+        assert_eq!(
+            lrc,
+            "[ar:A]\n[ti:T]\n[00:03.500]x\n[00:13.500]x\n[100:01.500]y\n"
+        );
+        let reparsed = Lyrics::from(&lrc);
+        assert_eq!(reparsed.timed_lines(), lyrics.timed_lines());
+        assert_eq!(reparsed.tags(), lyrics.tags());
+    }
+
+    #[test]
+    fn saturates_extreme_offsets() {
+        let lyrics = Lyrics::from("[offset:-9223372036854775808]\n[00:01.00]a");
+        assert_eq!(lyrics.offset(), i64::MIN);
+        assert_eq!(
+            lyrics.timed_lines()[0].time.as_millis(),
+            i64::MAX as u64 + 1000
+        );
+        let lyrics = Lyrics::from("[offset:9223372036854775807]\n[00:01.00]a");
+        assert_eq!(lyrics.timed_lines()[0].time.as_millis(), 0);
+        let max = LyricsDuration::from_millis(u64::MAX);
+        assert_eq!(max.shift(1), max);
+        assert_eq!(max + max, max);
+        assert_eq!(LyricsDuration::from(Duration::MAX), max);
+        assert_eq!(
+            LyricsDuration::from_millis(5) - max,
+            LyricsDuration::default()
+        );
+    }
+
+    #[test]
+    fn round_trips_words() {
+        let input = "[00:12.00]<00:12.00>Hello <00:12.50>world\n[00:14.00]plain\n";
+        let lyrics = Lyrics::from(input);
+        let line = &lyrics.timed_lines()[0];
+        assert_eq!(line.text, "Hello world");
+        assert_eq!(line.words.len(), 2);
+        assert_eq!(lyrics.to_lrc(LrcPrecision::Centiseconds), input);
+    }
+
+    #[test]
+    fn shifts_words_per_timestamp() {
+        let lyrics = Lyrics::from("[00:01.00][00:11.00]<00:01.00>a <00:01.50>b");
+        let times: Vec<Vec<u64>> = lyrics
+            .timed_lines()
+            .iter()
+            .map(|line| line.words.iter().map(|w| w.time.as_millis()).collect())
+            .collect();
+        assert_eq!(times, [[1000, 1500], [11_000, 11_500]]);
+        let mut lyrics = Lyrics::from("[offset:500]\n[00:01.00]<00:01.00>a <00:01.50>b");
+        assert_eq!(lyrics.timed_lines()[0].words[1].time.as_millis(), 1000);
+        lyrics.shift(-2000);
+        assert_eq!(lyrics.timed_lines()[0].words[1].time.as_millis(), 0);
+        // The text read stays as it was; writing gives the shifted times.
+        assert_eq!(lyrics.lines()[1], "[00:01.00]<00:01.00>a <00:01.50>b");
+        assert_eq!(
+            lyrics.to_lrc(LrcPrecision::Centiseconds),
+            "[offset:500]\n[00:00.50]<00:00.50>a <00:00.50>b\n"
+        );
+    }
+}
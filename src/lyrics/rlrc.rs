@@ -0,0 +1,320 @@
+//! The Rlrc markup:
+//!
+//! ```text
+//! <lyricist>Name/Name</lyricist>
+//! <composer>Name</composer>
+//! <wait start="00:00.34"></wait>
+//! <p name="Singer" hor="left" end="00:05.20">
+//!     <word><duration>00:04.00</duration><w>Hello</w></word>
+//!     <word-space><duration>00:04.60</duration><w> </w></word-space>
+//!     <word><duration>00:04.70</duration><w>world</w></word>
+//! </p>
+//! ```
+//!
+//! `<duration>` is the time the word starts at. `<wait>` is generated when
+//! writing, whenever more than 3 seconds pass between two lines or before the
+//! first one; it starts where the previous line ends, or at 00:00.00.
+
+use std::{fmt::Write, str::FromStr};
+
+use scraper::{ElementRef, Html};
+
+use super::{
+    lrc, Alignment, LineItem, LyricTag, LyricsDuration, RichLyrics, RichLyricsLine, Word, WordSpace,
+};
+use crate::{Error, Result};
+
+const WAIT_GAP: LyricsDuration = LyricsDuration::from_millis(3000);
+
+fn parse_time(time: &str) -> Result<LyricsDuration> {
+    lrc::parse_timestamp(time).ok_or_else(|| Error::FmtError(format!("Invalid time: {}", time)))
+}
+
+fn split_names(names: &str) -> Vec<String> {
+    names
+        .split('/')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn child_elements<'a>(element: ElementRef<'a>) -> impl Iterator<Item = ElementRef<'a>> {
+    element.children().filter_map(ElementRef::wrap)
+}
+
+fn parse_item(element: ElementRef) -> Result<(LyricsDuration, String)> {
+    let mut time = None;
+    let mut text = String::new();
+    for child in child_elements(element) {
+        match child.value().name() {
+            "duration" => time = Some(parse_time(&child.text().collect::<String>())?),
+            "w" => text = child.text().collect(),
+            _ => (),
+        }
+    }
+    let time = time.ok_or_else(|| Error::FmtError("Word without duration".into()))?;
+    Ok((time, text))
+}
+
+fn parse_line(element: ElementRef) -> Result<RichLyricsLine> {
+    let hor = match element.value().attr("hor") {
+        Some(hor) if hor.trim().eq_ignore_ascii_case("right") => Alignment::Right,
+        _ => Alignment::Left,
+    };
+    let end = element.value().attr("end").map(parse_time).transpose()?;
+    let mut items = Vec::new();
+    for child in child_elements(element) {
+        match child.value().name() {
+            "word" => {
+                let (time, word) = parse_item(child)?;
+                items.push(LineItem::Word(Word { time, word }));
+            }
+            "word-space" => {
+                let (time, space) = parse_item(child)?;
+                items.push(LineItem::Space(WordSpace { time, space }));
+            }
+            _ => (),
+        }
+    }
+    Ok(RichLyricsLine {
+        name: element.value().attr("name").map(String::from),
+        hor,
+        end,
+        items,
+    })
+}
+
+fn parse_tags(element: ElementRef, tags: &mut Vec<LyricTag>) -> Result<()> {
+    for child in child_elements(element) {
+        match child.value().name() {
+            "lyricist" => tags.push(LyricTag::Lyricist(split_names(
+                &child.text().collect::<String>(),
+            ))),
+            "composer" => tags.push(LyricTag::Composer(split_names(
+                &child.text().collect::<String>(),
+            ))),
+            "p" => tags.push(LyricTag::P(parse_line(child)?)),
+            "wait" => {
+                let start = child
+                    .value()
+                    .attr("start")
+                    .ok_or_else(|| Error::FmtError("Wait without start".into()))?;
+                tags.push(LyricTag::Wait(parse_time(start)?));
+            }
+            _ => parse_tags(child, tags)?,
+        }
+    }
+    Ok(())
+}
+
+impl FromStr for RichLyrics {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let html = Html::parse_fragment(s.trim_start_matches('\u{feff}'));
+        // Unclosed, stray or broken tags would silently move or drop text.
+        if let Some(error) = html.errors.first() {
+            return Err(Error::FmtError(format!("Invalid Rlrc markup: {}", error)));
+        }
+        let mut tags = Vec::new();
+        parse_tags(html.root_element(), &mut tags)?;
+        Ok(Self { tags })
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn write_item(rlrc: &mut String, tag: &str, time: LyricsDuration, text: &str) {
+    let _ = writeln!(
+        rlrc,
+        "    <{tag}><duration>{}</duration><w>{}</w></{tag}>",
+        time,
+        escape(text)
+    );
+}
+
+impl RichLyrics {
+    pub fn lines(&self) -> impl Iterator<Item = &RichLyricsLine> {
+        self.tags.iter().filter_map(|tag| match tag {
+            LyricTag::P(line) => Some(line),
+            _ => None,
+        })
+    }
+    /// Writes the markup. Existing `<wait>` tags are dropped and generated
+    /// again from the gaps between lines.
+    pub fn to_rlrc(&self) -> String {
+        let mut rlrc = String::new();
+        let mut last_end = LyricsDuration::default();
+        for tag in &self.tags {
+            match tag {
+                LyricTag::Wait(_) => (),
+                LyricTag::Lyricist(names) => {
+                    let _ = writeln!(rlrc, "<lyricist>{}</lyricist>", escape(&names.join("/")));
+                }
+                LyricTag::Composer(names) => {
+                    let _ = writeln!(rlrc, "<composer>{}</composer>", escape(&names.join("/")));
+                }
+                LyricTag::P(line) => {
+                    if let Some(start) = line.start() {
+                        if start - last_end > WAIT_GAP {
+                            let _ = writeln!(rlrc, "<wait start=\"{}\"></wait>", last_end);
+                        }
+                    }
+                    rlrc.push_str("<p");
+                    if let Some(name) = &line.name {
+                        let _ = write!(rlrc, " name=\"{}\"", escape(name));
+                    }
+                    rlrc.push_str(match line.hor {
+                        Alignment::Left => " hor=\"left\"",
+                        Alignment::Right => " hor=\"right\"",
+                    });
+                    if let Some(end) = line.end {
+                        let _ = write!(rlrc, " end=\"{}\"", end);
+                    }
+                    rlrc.push_str(">\n");
+                    for item in &line.items {
+                        match item {
+                            LineItem::Word(word) => {
+                                write_item(&mut rlrc, "word", word.time, &word.word)
+                            }
+                            LineItem::Space(space) => {
+                                write_item(&mut rlrc, "word-space", space.time, &space.space)
+                            }
+                        }
+                    }
+                    rlrc.push_str("</p>\n");
+                    if let Some(end) = line.end.or_else(|| line.items.last().map(LineItem::time)) {
+                        last_end = end;
+                    }
+                }
+            }
+        }
+        rlrc
+    }
+}
+
+impl std::fmt::Display for RichLyrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_rlrc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(millis: u64) -> LyricsDuration {
+        LyricsDuration::from_millis(millis)
+    }
+
+    fn word(millis: u64, word: &str) -> LineItem {
+        LineItem::Word(Word {
+            time: time(millis),
+            word: word.into(),
+        })
+    }
+
+    fn line(items: Vec<LineItem>, end: Option<u64>) -> LyricTag {
+        LyricTag::P(RichLyricsLine {
+            end: end.map(time),
+            items,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn parses_markup() {
+        let rlrc = "<lyricist>Ann/ Bob /</lyricist>\n<composer>Cid</composer>\n\
+            <p name=\"Dee &amp; Eve\" hor=\" RIGHT \" end=\"00:05.20\">\n\
+            <word><duration>00:04.00</duration><w>Hello</w></word>\n\
+            <word-space><duration>00:04.60</duration><w> </w></word-space>\n\
+            <word><duration>00:04.70</duration><w>world</w></word>\n</p>\n\
+            <p><word><duration>00:06.00</duration><w>Again</w></word></p>";
+        let lyrics: RichLyrics = rlrc.parse().unwrap();
+        assert_eq!(
+            lyrics.tags,
+            [
+                LyricTag::Lyricist(vec!["Ann".into(), "Bob".into()]),
+                LyricTag::Composer(vec!["Cid".into()]),
+                LyricTag::P(RichLyricsLine {
+                    name: Some("Dee & Eve".into()),
+                    hor: Alignment::Right,
+                    end: Some(time(5200)),
+                    items: vec![
+                        word(4000, "Hello"),
+                        LineItem::Space(WordSpace {
+                            time: time(4600),
+                            space: " ".into(),
+                        }),
+                        word(4700, "world"),
+                    ],
+                }),
+                line(vec![word(6000, "Again")], None),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_markup() {
+        for rlrc in [
+            "<p><word><duration>00:01.00</duration><w>Hi</w>",
+            "<p><word><duration>00:01.00</duration><w>Hi</word></p>",
+            "<wait start=\"00:01.00\"><p></p>",
+            "<p name=\"Ann></p>",
+            "<p <word></p>",
+            "</p>",
+            "<p><word><duration>1:xx</duration><w>Hi</w></word></p>",
+            "<p><word><w>Hi</w></word></p>",
+            "<p end=\"soon\"></p>",
+            "<wait></wait>",
+        ] {
+            assert!(rlrc.parse::<RichLyrics>().is_err(), "{}", rlrc);
+        }
+    }
+
+    #[test]
+    fn round_trips_with_waits() {
+        let lyrics = RichLyrics {
+            tags: vec![
+                LyricTag::Lyricist(vec!["Ann".into()]),
+                // Dropped and generated again.
+                LyricTag::Wait(time(100)),
+                line(vec![word(5000, "late"), word(5500, "start")], Some(6000)),
+                // Ends at its last word without an end time.
+                line(vec![word(8000, "close")], None),
+                line(vec![word(11_500, "far")], None),
+            ],
+        };
+        let rlrc = lyrics.to_rlrc();
+        let waits: Vec<_> = rlrc.lines().filter(|l| l.starts_with("<wait")).collect();
+        // The first line starts more than 3 s into the song, so the wait
+        // starts at the beginning.
+        assert_eq!(
+            waits,
+            [
+                "<wait start=\"00:00.00\"></wait>",
+                "<wait start=\"00:08.00\"></wait>"
+            ]
+        );
+
+        let parsed: RichLyrics = rlrc.parse().unwrap();
+        let mut expected = lyrics.tags.clone();
+        expected[1] = LyricTag::Wait(time(0));
+        expected.insert(4, LyricTag::Wait(time(8000)));
+        assert_eq!(parsed.tags, expected);
+        assert_eq!(parsed.to_rlrc(), rlrc);
+    }
+}
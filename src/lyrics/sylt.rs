@@ -0,0 +1,94 @@
+use id3::frame::{SynchronisedLyrics, SynchronisedLyricsType, TimestampFormat};
+
+use super::{Lyrics, LyricsDuration, LyricsLine};
+
+impl Lyrics {
+    /// Reads an ID3 SYLT frame. Timestamps counted in MPEG frames are not
+    /// supported.
+    pub fn from_sylt(sylt: &SynchronisedLyrics) -> Option<Lyrics> {
+        if sylt.timestamp_format != TimestampFormat::Ms {
+            return None;
+        }
+        let timed_lines = sylt
+            .content
+            .iter()
+            .map(|(millis, text)| LyricsLine {
+                time: LyricsDuration::from_millis(*millis as u64),
+                // Lines often start with the line break ending the previous one.
+                text: text.trim_matches(['\r', '\n']).to_owned(),
+                words: Vec::new(),
+            })
+            .collect();
+        Some(Lyrics::from_parts(Vec::new(), timed_lines))
+    }
+    /// Builds an ID3 SYLT frame from the timed lines. `lang` is an ISO-639-2
+    /// code such as `eng`. Times past the 32 bits of a SYLT timestamp, about
+    /// 49 days, are clamped.
+    pub fn to_sylt(&self, lang: &str, description: &str) -> SynchronisedLyrics {
+        SynchronisedLyrics {
+            lang: lang.into(),
+            timestamp_format: TimestampFormat::Ms,
+            content_type: SynchronisedLyricsType::Lyrics,
+            description: description.into(),
+            content: self
+                .timed_lines
+                .iter()
+                .map(|line| {
+                    let millis = u32::try_from(line.time.as_millis()).unwrap_or(u32::MAX);
+                    (millis, line.text.clone())
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sylt(format: TimestampFormat, content: &[(u32, &str)]) -> SynchronisedLyrics {
+        SynchronisedLyrics {
+            lang: "eng".into(),
+            timestamp_format: format,
+            content_type: SynchronisedLyricsType::Lyrics,
+            description: String::new(),
+            content: content.iter().map(|(t, s)| (*t, s.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn rejects_mpeg_frames() {
+        let frames = sylt(TimestampFormat::Mpeg, &[(10, "a")]);
+        assert!(Lyrics::from_sylt(&frames).is_none());
+    }
+
+    #[test]
+    fn trims_line_breaks() {
+        let frame = sylt(
+            TimestampFormat::Ms,
+            &[(2000, "\r\nsecond\r"), (1000, "first\n"), (3000, "\n\n")],
+        );
+        let lyrics = Lyrics::from_sylt(&frame).unwrap();
+        let lines: Vec<_> = lyrics
+            .timed_lines()
+            .iter()
+            .map(|line| (line.time.as_millis(), line.text.as_str()))
+            .collect();
+        assert_eq!(lines, [(1000, "first"), (2000, "second"), (3000, "")]);
+        assert_eq!(
+            lyrics.to_sylt("eng", "").content[1],
+            (2000, "second".into())
+        );
+    }
+
+    #[test]
+    fn clamps_long_times() {
+        let lyrics = Lyrics::from("[00:01.00]a\n[99999999:00.00]b");
+        let frame = lyrics.to_sylt("eng", "desc");
+        assert_eq!(frame.content, [(1000, "a".into()), (u32::MAX, "b".into())]);
+        assert_eq!(
+            (frame.lang.as_str(), frame.description.as_str()),
+            ("eng", "desc")
+        );
+    }
+}
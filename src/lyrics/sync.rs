@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use super::{LyricsLine, Word};
+
+/// Timed lines looked up by playback position. Lines sharing a timestamp,
+/// such as translations, are active together; the first of them is the one
+/// returned.
+#[derive(Debug, Clone, Copy)]
+pub struct SyncedLyrics<'a> {
+    lines: &'a [LyricsLine],
+    end: Option<Duration>,
+}
+
+impl<'a> SyncedLyrics<'a> {
+    pub(crate) fn new(lines: &'a [LyricsLine], end: Option<Duration>) -> Self {
+        Self { lines, end }
+    }
+    /// When the last line ends, usually the track duration. Defaults to the
+    /// `[length:]` tag.
+    pub fn with_end(mut self, end: Duration) -> Self {
+        self.end = Some(end);
+        self
+    }
+    /// Number of lines starting at or before `position`.
+    fn started(&self, position: Duration) -> usize {
+        self.lines
+            .partition_point(|line| Duration::from(line.time) <= position)
+    }
+    /// Index into `Lyrics::timed_lines` of the line active at `position`.
+    pub fn index_at(&self, position: Duration) -> Option<usize> {
+        let last = self.started(position).checked_sub(1)?;
+        let time = self.lines[last].time;
+        Some(self.lines.partition_point(|line| line.time < time))
+    }
+    pub fn line_at(&self, position: Duration) -> Option<&'a LyricsLine> {
+        self.index_at(position).map(|i| &self.lines[i])
+    }
+    /// The word of the active line sung at `position`, for enhanced LRC.
+    pub fn word_at(&self, position: Duration) -> Option<&'a Word> {
+        let words = &self.line_at(position)?.words;
+        let started = words.partition_point(|word| Duration::from(word.time) <= position);
+        words.get(started.checked_sub(1)?)
+    }
+    /// The first line starting after `position`.
+    pub fn next_line(&self, position: Duration) -> Option<&'a LyricsLine> {
+        self.lines.get(self.started(position))
+    }
+    /// How far `position` is into the active line, from 0.0 to 1.0. `None`
+    /// before the first line, or in the last one when the end is unknown.
+    pub fn progress(&self, position: Duration) -> Option<f32> {
+        let start = Duration::from(self.line_at(position)?.time);
+        let end = match self.next_line(position) {
+            Some(line) => Duration::from(line.time),
+            None => self.end?,
+        };
+        if end <= start {
+            return Some(1.0);
+        }
+        let progress = (position - start).as_secs_f64() / (end - start).as_secs_f64();
+        Some(progress.min(1.0) as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Lyrics;
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn lyrics() -> Lyrics {
+        Lyrics::from(
+            "[00:02.00]one\n[00:04.00]two\n[00:04.00]deux\n\
+             [00:06.00]<00:06.00>three <00:07.00>four\n",
+        )
+    }
+
+    fn text(line: Option<&LyricsLine>) -> Option<&str> {
+        line.map(|line| line.text.as_str())
+    }
+
+    #[test]
+    fn before_first_line() {
+        let lyrics = lyrics();
+        let synced = lyrics.synced();
+        assert_eq!(synced.index_at(ms(1999)), None);
+        assert_eq!(synced.line_at(ms(0)), None);
+        assert_eq!(synced.word_at(ms(0)), None);
+        assert_eq!(synced.progress(ms(1000)), None);
+        assert_eq!(text(synced.next_line(ms(0))), Some("one"));
+    }
+
+    #[test]
+    fn on_timestamps() {
+        let lyrics = lyrics();
+        let synced = lyrics.synced();
+        assert_eq!(synced.index_at(ms(2000)), Some(0));
+        assert_eq!(synced.progress(ms(2000)), Some(0.0));
+        assert_eq!(text(synced.next_line(ms(2000))), Some("two"));
+        assert_eq!(synced.progress(ms(3000)), Some(0.5));
+        // The line is active from its timestamp, not after it.
+        assert_eq!(text(synced.line_at(ms(3999))), Some("one"));
+        assert_eq!(text(synced.line_at(ms(6000))), Some("three four"));
+        assert_eq!(synced.word_at(ms(6999)).unwrap().word, "three ");
+        assert_eq!(synced.word_at(ms(7000)).unwrap().word, "four");
+    }
+
+    #[test]
+    fn shared_timestamps() {
+        let lyrics = lyrics();
+        let synced = lyrics.synced();
+        // Both lines at 00:04 are active; the first one is returned and the
+        // next line is the one after them.
+        assert_eq!(synced.index_at(ms(4000)), Some(1));
+        assert_eq!(synced.index_at(ms(5999)), Some(1));
+        assert_eq!(text(synced.line_at(ms(5000))), Some("two"));
+        assert_eq!(text(synced.next_line(ms(4000))), Some("three four"));
+        assert_eq!(synced.progress(ms(5000)), Some(0.5));
+    }
+
+    #[test]
+    fn last_line() {
+        let mut lyrics = lyrics();
+        let synced = lyrics.synced();
+        assert_eq!(synced.index_at(ms(60_000)), Some(3));
+        assert_eq!(synced.next_line(ms(6000)), None);
+        // Without a track length the last line never ends.
+        assert_eq!(synced.progress(ms(7000)), None);
+
+        lyrics.set_tag("length", "00:10.00");
+        let synced = lyrics.synced();
+        assert_eq!(synced.progress(ms(8000)), Some(0.5));
+        assert_eq!(synced.progress(ms(60_000)), Some(1.0));
+        let synced = synced.with_end(ms(6500));
+        assert_eq!(synced.progress(ms(6250)), Some(0.5));
+    }
+
+    #[test]
+    fn end_before_start() {
+        let lyrics = lyrics();
+        // An end at or before the last line's start, as with a wrong length.
+        for end in [ms(6000), ms(1000), Duration::ZERO] {
+            let synced = lyrics.synced().with_end(end);
+            for position in [ms(6000), ms(6001), ms(100_000)] {
+                let progress = synced.progress(position).unwrap();
+                assert!((0.0..=1.0).contains(&progress), "{}", progress);
+            }
+        }
+        let lines = [LyricsLine {
+            time: ms(0).into(),
+            text: "zero".into(),
+            words: Vec::new(),
+        }];
+        let synced = SyncedLyrics::new(&lines, Some(Duration::ZERO));
+        assert_eq!(synced.progress(Duration::ZERO), Some(1.0));
+    }
+}
@@ -0,0 +1,215 @@
+use std::{collections::HashMap, fmt, time::Duration};
+
+use super::{
+    lrc::{self, LrcLine},
+    Lyrics, LyricsDuration,
+};
+
+/// A problem found by [`Lyrics::validate`], on a line of [`Lyrics::lines`]
+/// counted from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub kind: DiagnosticKind,
+}
+
+/// Times are given as written in the file, before `[offset:]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// The line starts before the timed line above it.
+    OutOfOrder {
+        time: LyricsDuration,
+        previous: LyricsDuration,
+    },
+    /// The line starts after the end of the track.
+    PastEnd {
+        time: LyricsDuration,
+        end: LyricsDuration,
+    },
+    /// An earlier line has the same timestamp. Bilingual lyrics do this on
+    /// purpose.
+    DuplicateTimestamp {
+        time: LyricsDuration,
+        first_line: usize,
+    },
+    /// A timestamp without text.
+    EmptyLine { time: LyricsDuration },
+    /// A bracketed line that is neither a tag nor a timestamp, or an
+    /// `[offset:]` or `[length:]` tag with an unreadable value.
+    MalformedTag,
+    /// Text without a timestamp in timed lyrics.
+    UntimedLine,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            DiagnosticKind::OutOfOrder { time, previous } => {
+                write!(f, "{} is earlier than the line before, {}", time, previous)
+            }
+            DiagnosticKind::PastEnd { time, end } => {
+                write!(f, "{} is past the end of the track, {}", time, end)
+            }
+            DiagnosticKind::DuplicateTimestamp { time, first_line } => {
+                write!(f, "{} is already used on line {}", time, first_line)
+            }
+            DiagnosticKind::EmptyLine { time } => write!(f, "{} has no text", time),
+            DiagnosticKind::MalformedTag => f.write_str("malformed tag"),
+            DiagnosticKind::UntimedLine => f.write_str("line has no timestamp"),
+        }
+    }
+}
+
+impl Lyrics {
+    /// Checks the LRC text the lyrics were read from. `duration` is the
+    /// length of the track, falling back to the `[length:]` tag; without
+    /// either, times are not checked against the end. Lines with several
+    /// timestamps are ordered by their first one.
+    pub fn validate(&self, duration: Option<Duration>) -> Vec<Diagnostic> {
+        let end = duration.map(LyricsDuration::from).or(self.length());
+        let offset = self.offset();
+        let has_timed_lines = !self.timed_lines.is_empty();
+        let mut diagnostics = Vec::new();
+        let mut seen = HashMap::new();
+        let mut previous = None;
+        for (i, text) in self.lines.iter().enumerate() {
+            let line = i + 1;
+            let mut push = |kind| diagnostics.push(Diagnostic { line, kind });
+            match lrc::parse_line(text) {
+                LrcLine::Tag(key, value) => {
+                    let valid = match key.to_ascii_lowercase().as_str() {
+                        "offset" => value.trim_start_matches('+').parse::<i64>().is_ok(),
+                        "length" => lrc::parse_timestamp(value).is_some(),
+                        _ => true,
+                    };
+                    if !valid {
+                        push(DiagnosticKind::MalformedTag);
+                    }
+                }
+                LrcLine::Timed(times, rest) => {
+                    let first = times[0];
+                    if let Some(previous) = previous.filter(|&previous| first < previous) {
+                        push(DiagnosticKind::OutOfOrder {
+                            time: first,
+                            previous,
+                        });
+                    }
+                    previous = Some(first);
+                    for &time in &times {
+                        if let Some(end) =
+                            end.filter(|&end| time.shift(offset.saturating_neg()) > end)
+                        {
+                            push(DiagnosticKind::PastEnd { time, end });
+                        }
+                        match seen.get(&time) {
+                            Some(&first_line) => {
+                                push(DiagnosticKind::DuplicateTimestamp { time, first_line })
+                            }
+                            None => {
+                                seen.insert(time, line);
+                            }
+                        }
+                    }
+                    if lrc::parse_words(rest, first).0.is_empty() {
+                        push(DiagnosticKind::EmptyLine { time: first });
+                    }
+                }
+                LrcLine::Text if text.starts_with('[') => push(DiagnosticKind::MalformedTag),
+                LrcLine::Text if has_timed_lines && !text.is_empty() => {
+                    push(DiagnosticKind::UntimedLine)
+                }
+                LrcLine::Text => (),
+            }
+        }
+        diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> LyricsDuration {
+        LyricsDuration::from_millis(millis)
+    }
+
+    fn kinds(lrc: &str, duration: Option<Duration>) -> Vec<(usize, DiagnosticKind)> {
+        Lyrics::from(lrc)
+            .validate(duration)
+            .into_iter()
+            .map(|d| (d.line, d.kind))
+            .collect()
+    }
+
+    #[test]
+    fn accepts_clean_lyrics() {
+        let lrc = "[ti:Song]\n[offset:+100]\n\n[00:01.00]a\n[00:02.00][00:03.00]b";
+        assert_eq!(kinds(lrc, Some(Duration::from_secs(10))), []);
+        assert_eq!(kinds("plain\ntext", None), []);
+    }
+
+    #[test]
+    fn reports_timing_problems() {
+        let lrc = "[00:05.00]a\n[00:02.00]b\n[00:05.00]c\n[00:09.00]";
+        assert_eq!(
+            kinds(lrc, Some(Duration::from_secs(8))),
+            [
+                (
+                    2,
+                    DiagnosticKind::OutOfOrder {
+                        time: ms(2000),
+                        previous: ms(5000)
+                    }
+                ),
+                (
+                    3,
+                    DiagnosticKind::DuplicateTimestamp {
+                        time: ms(5000),
+                        first_line: 1
+                    }
+                ),
+                (
+                    4,
+                    DiagnosticKind::PastEnd {
+                        time: ms(9000),
+                        end: ms(8000)
+                    }
+                ),
+                (4, DiagnosticKind::EmptyLine { time: ms(9000) }),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_end_with_offset_and_length() {
+        // The offset makes the line start at 7s.
+        assert_eq!(
+            kinds("[offset:2000]\n[00:09.00]a", Some(Duration::from_secs(8))),
+            []
+        );
+        let past = kinds("[length:00:08.00]\n[00:09.00]a", None);
+        assert_eq!(past.len(), 1);
+        assert!(matches!(past[0], (2, DiagnosticKind::PastEnd { .. })));
+        let extreme = "[offset:-9223372036854775808]\n[00:01.00]a";
+        // A negative offset delays the lyrics, here past any end.
+        let past = kinds(extreme, Some(Duration::from_secs(8)));
+        assert!(matches!(past[..], [(2, DiagnosticKind::PastEnd { .. })]));
+    }
+
+    #[test]
+    fn reports_malformed_lines() {
+        let lrc = "[offset:soon]\n[length:x]\n[00:01.00]a\n[garbage\nuntimed";
+        assert_eq!(
+            kinds(lrc, None),
+            [
+                (1, DiagnosticKind::MalformedTag),
+                (2, DiagnosticKind::MalformedTag),
+                (4, DiagnosticKind::MalformedTag),
+                (5, DiagnosticKind::UntimedLine),
+            ]
+        );
+        let diagnostic = Lyrics::from(lrc).validate(None).remove(3);
+        assert_eq!(diagnostic.to_string(), "line 5: line has no timestamp");
+    }
+}